@@ -0,0 +1,346 @@
+// API HTTP/JSON local para extensões de navegador, scripts e outras ferramentas
+//
+//   POST /downloads          {"url": "...", "profile": "..."}  adiciona à fila
+//   GET  /downloads                                           estado da fila
+//   POST /downloads/cancel   {"url": "..."}                    cancela ou remove da fila
+//   POST /downloads/resume   {"url": "..."}                    retoma um download pausado
+//   POST /downloads/retry    {"url": "..."}                    tenta de novo um download que falhou
+//   GET  /history?q=texto                                     histórico (busca opcional)
+//   GET  /config?key=a.b                                      configurações (todas sem key)
+//   POST /config             {"key": "a.b", "value": ...}     altera uma configuração
+//
+// POST /downloads também aceita "output_dir", "filename_template", "subtitles" e
+// "post_processors" só para aquele download (ver output.rs).
+//
+// Só escuta em 127.0.0.1 e toda requisição precisa de "Authorization: Bearer <token>".
+// As mesmas rotas atendem a CLI pelo socket local (ipc.rs), sem token.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use subtle::ConstantTimeEq;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+use crate::app_core::{Core, QueueOutcome};
+use crate::output::OutputOptions;
+use crate::settings::ApiOptions;
+use crate::validation;
+
+const MAX_HEADER_LINES: usize = 100;
+const MAX_BODY_BYTES: usize = 64 * 1024;
+// Conexão que não manda a requisição inteira nesse prazo é fechada
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub query: HashMap<String, String>,
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    fn json<T: for<'de> Deserialize<'de>>(&self) -> Result<T, Response> {
+        serde_json::from_slice(&self.body).map_err(|e| Response::error(400, &format!("JSON inválido: {}", e)))
+    }
+
+    fn authorized(&self, token: &str) -> bool {
+        !token.is_empty()
+            && self
+                .headers
+                .get("authorization")
+                .and_then(|value| value.strip_prefix("Bearer "))
+                // Tempo constante: a comparação não revela quantos caracteres acertaram
+                .is_some_and(|given| bool::from(given.trim().as_bytes().ct_eq(token.as_bytes())))
+    }
+}
+
+pub struct Response {
+    pub status: u16,
+    pub body: Value,
+}
+
+impl Response {
+    pub fn json(status: u16, body: impl Serialize) -> Self {
+        Self {
+            status,
+            body: serde_json::to_value(body).unwrap_or(Value::Null),
+        }
+    }
+
+    pub fn error(status: u16, message: &str) -> Self {
+        Self::json(status, json!({ "error": message }))
+    }
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        202 => "Accepted",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        408 => "Request Timeout",
+        409 => "Conflict",
+        _ => "Error",
+    }
+}
+
+// Lê uma requisição HTTP/1.1 simples (sem chunked); None se a conexão fechar ou vier malformada
+pub async fn read_request<S: AsyncRead + Unpin>(stream: &mut BufReader<S>) -> Option<Request> {
+    let mut line = String::new();
+    stream.read_line(&mut line).await.ok()?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_uppercase();
+    let target = parts.next()?.to_string();
+
+    let mut headers = HashMap::new();
+    for _ in 0..MAX_HEADER_LINES {
+        let mut header = String::new();
+        if stream.read_line(&mut header).await.ok()? == 0 {
+            return None;
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+        }
+    }
+
+    let length: usize = headers
+        .get("content-length")
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(0);
+    if length > MAX_BODY_BYTES {
+        return None;
+    }
+
+    let mut body = vec![0; length];
+    stream.read_exact(&mut body).await.ok()?;
+
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path.to_string(), query),
+        None => (target.clone(), ""),
+    };
+
+    Some(Request {
+        method,
+        path,
+        query: url::form_urlencoded::parse(query.as_bytes()).into_owned().collect(),
+        headers,
+        body,
+    })
+}
+
+pub async fn write_response<S: AsyncWrite + Unpin>(stream: &mut S, response: &Response) -> std::io::Result<()> {
+    let body = response.body.to_string();
+    let head = format!(
+        "HTTP/1.1 {} {}\r\n\
+         Content-Type: application/json; charset=utf-8\r\n\
+         Content-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\n\
+         Access-Control-Allow-Headers: Authorization, Content-Type\r\n\
+         Access-Control-Allow-Methods: GET, POST, OPTIONS\r\n\
+         Connection: close\r\n\r\n",
+        response.status,
+        reason_phrase(response.status),
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body.as_bytes()).await?;
+    stream.flush().await
+}
+
+#[derive(Deserialize)]
+struct AddBody {
+    url: String,
+    profile: Option<String>,
+    // output_dir, filename_template, subtitles e post_processors opcionais
+    #[serde(flatten)]
+    output: OutputOptions,
+}
+
+#[derive(Deserialize)]
+struct UrlBody {
+    url: String,
+}
+
+#[derive(Deserialize)]
+struct ConfigBody {
+    key: String,
+    value: Value,
+}
+
+fn route(core: &Arc<Core>, request: &Request) -> Result<Response, Response> {
+    let queue = &core.queue;
+    let history = &core.history;
+
+    match (request.method.as_str(), request.path.trim_end_matches('/')) {
+        ("POST", "/downloads") => {
+            let body: AddBody = request.json()?;
+            let outcome = core
+                .queue_download_with(body.url.trim(), body.profile, body.output)
+                .map_err(|errors| {
+                    Response::json(400, json!({ "error": validation::describe(&errors), "fields": errors }))
+                })?;
+            if outcome == QueueOutcome::InvalidUrl {
+                return Err(Response::error(400, "URL inválida: use um endereço http:// ou https://"));
+            }
+            let status = if outcome.accepted() { 202 } else { 409 };
+            Ok(Response::json(status, json!({ "result": outcome })))
+        }
+        ("GET", "/downloads") => Ok(Response::json(200, queue.get_status())),
+        ("POST", "/downloads/cancel") => {
+            let body: UrlBody = request.json()?;
+            if !queue.cancel(&body.url) {
+                return Err(Response::error(404, "URL não está na fila"));
+            }
+            core.emit_status();
+            Ok(Response::json(200, json!({ "cancelled": true })))
+        }
+        ("POST", "/downloads/resume") => {
+            let body: UrlBody = request.json()?;
+            if !queue.resume(&body.url) {
+                return Err(Response::error(404, "URL não está entre os pausados"));
+            }
+            core.emit_status();
+            Ok(Response::json(200, json!({ "resumed": true })))
+        }
+        ("POST", "/downloads/retry") => {
+            let body: UrlBody = request.json()?;
+            if !queue.retry_failed(&body.url) {
+                return Err(Response::error(404, "URL não está entre as falhas"));
+            }
+            core.emit_status();
+            Ok(Response::json(200, json!({ "retried": true })))
+        }
+        ("GET", "/history") => {
+            let entries = match request.query.get("q").map(|q| q.trim()).filter(|q| !q.is_empty()) {
+                Some(query) => history.search(query),
+                None => history.entries(),
+            };
+            Ok(Response::json(200, entries))
+        }
+        ("GET", "/config") => {
+            let key = request.query.get("key").map(|key| key.as_str());
+            core.config_value(key)
+                .map(|value| Response::json(200, value))
+                .ok_or_else(|| Response::error(404, "configuração desconhecida"))
+        }
+        ("POST", "/config") => {
+            let body: ConfigBody = request.json()?;
+            core.set_config_value(&body.key, body.value)
+                .map_err(|e| Response::error(400, &e))?;
+            Ok(Response::json(200, json!({ "saved": true })))
+        }
+        _ => Err(Response::error(404, "rota não encontrada")),
+    }
+}
+
+// `token` None: conexão já confiável (socket local protegido pelas permissões do arquivo)
+pub fn handle(core: &Arc<Core>, token: Option<&str>, request: &Request) -> Response {
+    // Preflight CORS de extensões e páginas: não leva o token
+    if request.method == "OPTIONS" {
+        return Response::json(200, json!({}));
+    }
+
+    if token.is_some_and(|token| !request.authorized(token)) {
+        return Response::error(401, "token ausente ou inválido");
+    }
+    route(core, request).unwrap_or_else(|error| error)
+}
+
+// Servidor em execução e as opções com que foi iniciado. Precisa rodar dentro de um runtime tokio
+#[derive(Default)]
+pub struct ApiServer {
+    running: Mutex<Option<(ApiOptions, JoinHandle<()>)>>,
+}
+
+impl ApiServer {
+    // Reinicia o servidor só quando as opções mudam
+    pub fn apply(&self, core: &Arc<Core>, options: &ApiOptions) {
+        let mut running = self.running.lock().unwrap();
+
+        if running.as_ref().is_some_and(|(current, _)| current == options) {
+            return;
+        }
+
+        if let Some((_, task)) = running.take() {
+            task.abort();
+        }
+
+        if options.enabled {
+            let task = tokio::spawn(serve(core.clone(), options.clone()));
+            *running = Some((options.clone(), task));
+        }
+    }
+}
+
+async fn serve(core: Arc<Core>, options: ApiOptions) {
+    let listener = match TcpListener::bind(("127.0.0.1", options.port)).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            core.notify("API Local", &format!("Não foi possível usar a porta {}: {}", options.port, e));
+            return;
+        }
+    };
+
+    while let Ok((stream, _)) = listener.accept().await {
+        tokio::spawn(serve_connection(core.clone(), Some(options.token.clone()), stream));
+    }
+}
+
+// Atende uma requisição e fecha a conexão; usado também pelo socket local
+pub async fn serve_connection<S: AsyncRead + AsyncWrite + Unpin>(core: Arc<Core>, token: Option<String>, stream: S) {
+    let mut stream = BufReader::new(stream);
+    let response = match tokio::time::timeout(READ_TIMEOUT, read_request(&mut stream)).await {
+        Ok(Some(request)) => handle(&core, token.as_deref(), &request),
+        Ok(None) => Response::error(400, "requisição inválida"),
+        Err(_) => Response::error(408, "tempo esgotado lendo a requisição"),
+    };
+    let _ = write_response(stream.get_mut(), &response).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(authorization: Option<&str>) -> Request {
+        Request {
+            method: "GET".to_string(),
+            path: "/downloads".to_string(),
+            query: HashMap::new(),
+            headers: authorization
+                .map(|value| ("authorization".to_string(), value.to_string()))
+                .into_iter()
+                .collect(),
+            body: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn requires_the_exact_bearer_token() {
+        assert!(request(Some("Bearer segredo")).authorized("segredo"));
+        assert!(!request(Some("Bearer segred")).authorized("segredo"));
+        assert!(!request(Some("Bearer segredo2")).authorized("segredo"));
+        assert!(!request(Some("segredo")).authorized("segredo"));
+        assert!(!request(None).authorized("segredo"));
+        assert!(!request(Some("Bearer ")).authorized(""));
+    }
+
+    #[tokio::test]
+    async fn reads_a_request_with_body() {
+        let raw = "POST /downloads?x=1 HTTP/1.1\r\nContent-Length: 2\r\nAuthorization: Bearer t\r\n\r\n{}";
+        let mut stream = BufReader::new(raw.as_bytes());
+        let request = read_request(&mut stream).await.unwrap();
+        assert_eq!((request.method.as_str(), request.path.as_str()), ("POST", "/downloads"));
+        assert_eq!(request.query.get("x").map(String::as_str), Some("1"));
+        assert_eq!(request.body, b"{}");
+    }
+}
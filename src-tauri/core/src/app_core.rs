@@ -0,0 +1,490 @@
+// Núcleo do aplicativo: configurações, histórico, fila e agendador, sem depender de janelas.
+// O tray e o daemon headless são clientes do mesmo núcleo e recebem os eventos por um EventSink
+
+use chrono::{Local, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::future::Future;
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+
+use crate::backends::{self, BackendKind};
+use crate::downloader;
+use crate::errors::DownloadFailure;
+use crate::extractors;
+use crate::history::HistoryManager;
+use crate::output::OutputOptions;
+use crate::playlist;
+use crate::queue::{ActiveOutcome, DownloadGroup, DownloadProgress, DownloadQueue, DownloadRequest, DownloadStatus, StopReason};
+use crate::rules;
+use crate::settings::{self, Settings, SettingsManager};
+use crate::validation::{self, FieldError};
+
+// Saída dos eventos do núcleo: notificações, janelas abertas, log do daemon
+pub trait EventSink: Send + Sync {
+    // Só é chamado com as notificações ativadas nas configurações
+    fn notify(&self, title: &str, body: &str);
+
+    fn status_changed(&self, status: &DownloadStatus);
+
+    fn progress(&self, url: &str, progress: &DownloadProgress);
+
+    fn completion_sound(&self) {}
+
+    // Configurações salvas pela interface, pela CLI ou pela API
+    fn settings_changed(&self, _core: &Arc<Core>, _settings: &Settings) {}
+}
+
+// Resultado de um pedido de download (tray, atalhos, CLI ou API local)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueOutcome {
+    Queued,
+    Expanding,
+    AlreadyQueued,
+    AlreadyDownloaded,
+    NoUrl,
+    // Só endereços http(s); o texto vai como argumento para o yt-dlp
+    InvalidUrl,
+}
+
+impl QueueOutcome {
+    pub fn accepted(self) -> bool {
+        matches!(self, QueueOutcome::Queued | QueueOutcome::Expanding)
+    }
+}
+
+pub struct Core {
+    pub settings: SettingsManager,
+    pub history: HistoryManager,
+    pub queue: DownloadQueue,
+    pub events: Arc<dyn EventSink>,
+}
+
+impl Core {
+    // Núcleo com os arquivos na pasta de configuração do usuário
+    pub fn new(events: Arc<dyn EventSink>) -> Arc<Self> {
+        Self::open(&settings::app_config_dir(), events)
+    }
+
+    // settings.json, history.json e queue.json em `config_dir`
+    pub fn open(config_dir: &Path, events: Arc<dyn EventSink>) -> Arc<Self> {
+        let settings = SettingsManager::open(config_dir);
+
+        // Token da API local gerado na primeira execução e salvo nas configurações
+        if settings.get().api.token.is_empty() {
+            let mut updated = settings.get().as_ref().clone();
+            updated.api.token = settings::generate_token();
+            settings.update(updated);
+        }
+
+        Arc::new(Self {
+            settings,
+            history: HistoryManager::open(config_dir),
+            queue: DownloadQueue::with_persistence(config_dir),
+            events,
+        })
+    }
+
+    pub fn notify(&self, title: &str, body: &str) {
+        if self.settings.get().enable_notifications {
+            self.events.notify(title, body);
+        }
+    }
+
+    // Emite o estado completo da fila para as janelas abertas
+    pub fn emit_status(&self) {
+        self.events.status_changed(&self.queue.get_status());
+    }
+
+    pub fn update_settings(self: &Arc<Self>, mut settings: Settings) {
+        if settings.api.token.is_empty() {
+            settings.api.token = settings::generate_token();
+        }
+        self.settings.update(settings.clone());
+        self.events.settings_changed(self, &settings);
+        // max_concurrent pode ter aumentado
+        self.queue.wake();
+    }
+
+    // Configurações vindas da interface: nada é salvo se algum campo estiver errado.
+    // O modelo do nome do arquivo passa pelo próprio yt-dlp quando ele está disponível
+    pub async fn save_settings(self: &Arc<Self>, mut settings: Settings) -> Result<(), Vec<FieldError>> {
+        let mut errors = validation::validate(&mut settings);
+
+        if !errors.iter().any(|error| error.field == "filename_template") {
+            if let Err(message) = validation::preview_filename(&settings.ytdlp_path, &settings.filename_template).await {
+                errors.push(FieldError::new("filename_template", message));
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        self.update_settings(settings);
+        Ok(())
+    }
+
+    pub fn config_value(&self, key: Option<&str>) -> Option<Value> {
+        settings::get_value(&self.settings.get(), key)
+    }
+
+    pub fn set_config_value(self: &Arc<Self>, key: &str, value: Value) -> Result<(), String> {
+        let current = self.settings.get();
+        let mut settings = settings::set_value(&current, key, value)?;
+        // Os programas executados só mudam pela janela de configurações ou no settings.json;
+        // pela API, quem tivesse o token poderia rodar qualquer comando
+        if settings::executables(&settings) != settings::executables(&current) {
+            return Err("O caminho dos programas de download só pode ser alterado nas Configurações do app".to_string());
+        }
+        let errors = validation::validate(&mut settings);
+        if !errors.is_empty() {
+            return Err(validation::describe(&errors));
+        }
+        self.update_settings(settings);
+        Ok(())
+    }
+
+    // Log parcial do download em andamento ou, se já terminou, o da última tentativa
+    pub fn download_log(&self, url: &str) -> Option<String> {
+        self.queue.log(url).or_else(|| self.history.latest_log(url))
+    }
+
+    // Avisa sobre downloads restaurados da sessão anterior
+    pub fn notify_restored(&self) {
+        let restored = self.queue.get_status();
+        if restored.queued + restored.paused > 0 {
+            self.notify(
+                "Fila Restaurada",
+                &format!(
+                    "{} na fila, {} pausado{}",
+                    restored.queued,
+                    restored.paused,
+                    if restored.paused > 1 { "s" } else { "" }
+                ),
+            );
+        }
+    }
+
+    // Adiciona a URL à fila, respeitando o histórico, e avisa o usuário
+    pub fn queue_download(self: &Arc<Self>, url: &str, profile: Option<String>) -> QueueOutcome {
+        self.queue_and_notify(url, profile, OutputOptions::default())
+    }
+
+    // Como queue_download, com pasta, modelo, legendas ou pós-processamento próprios do pedido.
+    // Pasta e modelo passam pela mesma validação das configurações antes de entrar na fila
+    pub fn queue_download_with(
+        self: &Arc<Self>,
+        url: &str,
+        profile: Option<String>,
+        output: OutputOptions,
+    ) -> Result<QueueOutcome, Vec<FieldError>> {
+        let errors = validation::validate_output(&output);
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        Ok(self.queue_and_notify(url, profile, output))
+    }
+
+    fn queue_and_notify(self: &Arc<Self>, url: &str, profile: Option<String>, output: OutputOptions) -> QueueOutcome {
+        let outcome = self.enqueue(url, profile, output);
+
+        match outcome {
+            QueueOutcome::NoUrl => self.notify("Sem URL", "Copie uma URL de vídeo primeiro"),
+            QueueOutcome::InvalidUrl => self.notify("URL Inválida", "Use um endereço http:// ou https://"),
+            QueueOutcome::AlreadyDownloaded => {
+                self.notify("Vídeo Já Baixado", "Este vídeo já foi baixado anteriormente")
+            }
+            QueueOutcome::Queued => self.notify_queued(),
+            QueueOutcome::Expanding | QueueOutcome::AlreadyQueued => {}
+        }
+        outcome
+    }
+
+    // Várias URLs de uma vez (histórico da área de transferência), com um único aviso
+    pub fn queue_downloads(self: &Arc<Self>, urls: &[String], profile: Option<String>) -> Vec<QueueOutcome> {
+        let outcomes: Vec<QueueOutcome> = urls
+            .iter()
+            .map(|url| self.enqueue(url, profile.clone(), OutputOptions::default()))
+            .collect();
+
+        let accepted = outcomes.iter().filter(|outcome| outcome.accepted()).count();
+        let downloaded = outcomes
+            .iter()
+            .filter(|outcome| **outcome == QueueOutcome::AlreadyDownloaded)
+            .count();
+
+        if !outcomes.is_empty() {
+            let mut body = format!(
+                "{} de {} URL{} na fila",
+                accepted,
+                outcomes.len(),
+                if outcomes.len() > 1 { "s" } else { "" }
+            );
+            if downloaded > 0 {
+                body.push_str(&format!(
+                    "\n{} já baixado{} anteriormente",
+                    downloaded,
+                    if downloaded > 1 { "s" } else { "" }
+                ));
+            }
+            self.notify("Adicionado à Fila", &body);
+        }
+        outcomes
+    }
+
+    fn enqueue(self: &Arc<Self>, url: &str, profile: Option<String>, output: OutputOptions) -> QueueOutcome {
+        let settings = self.settings.get();
+
+        if url.is_empty() {
+            return QueueOutcome::NoUrl;
+        }
+        if extractors::url_host(url).is_none() {
+            return QueueOutcome::InvalidUrl;
+        }
+
+        if settings.check_duplicates && self.history.contains(url) {
+            return QueueOutcome::AlreadyDownloaded;
+        }
+
+        // Opções da regra do site valem quando não há escolha explícita
+        let rule = rules::allowed_rule(&settings.site_rules, url);
+        let request = DownloadRequest {
+            output: match rule {
+                Some(rule) => output.or(&rule.output),
+                None => output,
+            },
+            ..DownloadRequest::new(
+                url.to_string(),
+                profile.or_else(|| rule.and_then(|r| r.profile.clone())),
+            )
+        };
+
+        // Só o yt-dlp expande playlists; os outros programas recebem a URL como está
+        if settings.playlist.expand
+            && playlist::is_playlist_url(url)
+            && backends::detect(&settings, url) == BackendKind::YtDlp
+        {
+            let core = self.clone();
+            spawn_task(async move { core.queue_playlist(request).await });
+            return QueueOutcome::Expanding;
+        }
+
+        if self.queue.add(request) {
+            self.emit_status();
+            QueueOutcome::Queued
+        } else {
+            QueueOutcome::AlreadyQueued
+        }
+    }
+
+    fn notify_queued(&self) {
+        let status = self.queue.get_status();
+        self.notify(
+            "Adicionado à Fila",
+            &format!(
+                "{} ativo{}, {} na fila",
+                status.active,
+                if status.active > 1 { "s" } else { "" },
+                status.queued
+            ),
+        );
+    }
+
+    // Pré-consulta com --flat-playlist e enfileira cada vídeo no mesmo grupo
+    async fn queue_playlist(&self, parent: DownloadRequest) {
+        let settings = self.settings.get();
+
+        self.notify("Expandindo Playlist", "Obtendo a lista de vídeos...");
+
+        let playlist = match playlist::expand_playlist(&settings.ytdlp_path, &parent.url, &settings.playlist).await {
+            Ok(Some(playlist)) => playlist,
+            Ok(None) => {
+                if self.queue.add(parent) {
+                    self.emit_status();
+                    self.notify_queued();
+                }
+                return;
+            }
+            Err(e) => {
+                self.notify("Erro na Playlist", &e);
+                return;
+            }
+        };
+
+        let found = playlist.entries.len();
+        let requests: Vec<DownloadRequest> = playlist
+            .entries
+            .into_iter()
+            .filter(|entry| !(settings.playlist.skip_downloaded && self.history.contains(&entry.url)))
+            .map(|entry| DownloadRequest {
+                url: entry.url,
+                title: entry.title,
+                group: Some(parent.url.clone()),
+                ..parent.clone()
+            })
+            .collect();
+
+        let group = DownloadGroup {
+            id: parent.url.clone(),
+            title: playlist.title.clone(),
+            total: requests.len(),
+            finished: 0,
+            failed: 0,
+        };
+        let added = self.queue.add_group(group, requests);
+        self.emit_status();
+
+        self.notify(
+            "Playlist Adicionada",
+            &format!(
+                "{}: {} de {} vídeo{} na fila",
+                playlist.title,
+                added,
+                found,
+                if found > 1 { "s" } else { "" }
+            ),
+        );
+    }
+
+    // Agendador da fila: acorda com itens novos, downloads finalizados, configurações alteradas
+    // ou no prazo de uma nova tentativa, e preenche todas as vagas livres de uma vez.
+    // Precisa rodar dentro de um runtime tokio
+    pub async fn run_scheduler(self: Arc<Self>) {
+        let queue = &self.queue;
+
+        loop {
+            let settings = self.settings.get();
+            let now = Local::now().time();
+            let allowed = settings.bandwidth.downloads_allowed(now);
+
+            // Fora do horário permitido, os downloads em andamento voltam para a fila
+            if !allowed {
+                queue.requeue_active();
+            }
+
+            let mut started = Vec::new();
+            loop {
+                // Item fixado começa mesmo com todas as vagas ocupadas ou fora do horário
+                let next = match queue.take_pinned(&settings.host_limits) {
+                    Some(request) => Some(request),
+                    None if allowed && queue.can_start(settings.max_concurrent) => {
+                        queue.get_next(&settings.host_limits)
+                    }
+                    None => None,
+                };
+                let Some(request) = next else {
+                    break;
+                };
+
+                queue.mark_active(request.clone());
+                started.push(request);
+            }
+
+            // Limite dividido igualmente entre os ativos, inclusive os que acabaram de começar
+            let share = settings.bandwidth.share(now, queue.active_count());
+            queue.rebalance(share);
+
+            if !started.is_empty() {
+                self.emit_status();
+            }
+
+            for request in started {
+                queue.set_rate(&request.url, share);
+
+                if !request.restarted {
+                    let status = queue.get_status();
+                    self.notify(
+                        "Download Iniciado",
+                        &format!(
+                            "{} ativo{}, {} na fila",
+                            status.active,
+                            if status.active > 1 { "s" } else { "" },
+                            status.queued
+                        ),
+                    );
+                }
+
+                tokio::spawn(self.clone().run_download(request, settings.clone(), share));
+            }
+
+            queue
+                .wait_for_change(&settings.host_limits, settings.bandwidth.next_change_in(now))
+                .await;
+        }
+    }
+
+    async fn run_download(self: Arc<Self>, request: DownloadRequest, settings: Arc<Settings>, rate_limit: Option<u64>) {
+        let queue = &self.queue;
+        let result = downloader::download_video(&self, &request, &settings, rate_limit).await;
+        let stopped = queue.take_stop_request(&request.url);
+
+        let outcome = match (stopped, result) {
+            (Some(StopReason::Pause), _) => ActiveOutcome::Paused,
+            (Some(StopReason::Requeue), _) => ActiveOutcome::Requeue,
+            (Some(StopReason::Cancel), _) | (None, Ok(())) => ActiveOutcome::Done,
+            (None, Err(failure)) => self.failure_outcome(&settings, &request, failure),
+        };
+
+        // Itens que vão tentar de novo continuam pendentes no grupo
+        if let Some(group) = &request.group {
+            match (&outcome, stopped) {
+                (ActiveOutcome::Paused | ActiveOutcome::Retry(_) | ActiveOutcome::Requeue, _) => {}
+                (_, Some(StopReason::Cancel)) => queue.forget_group_item(group),
+                (ActiveOutcome::Failed(_), _) => queue.finish_group_item(group, false),
+                _ => queue.finish_group_item(group, true),
+            }
+        }
+
+        // Por último: ao sair, o app espera a lista de ativos esvaziar
+        queue.remove_active(&request.url, outcome);
+        self.emit_status();
+    }
+
+    // Decide entre nova tentativa automática e a lista de falhas, avisando o usuário
+    fn failure_outcome(&self, settings: &Settings, request: &DownloadRequest, failure: DownloadFailure) -> ActiveOutcome {
+        let attempts = request.attempts + 1;
+        let name = request.title.as_deref().unwrap_or(&request.url);
+
+        if settings.retry.should_retry(failure.kind, attempts) {
+            let delay = settings.retry.delay(attempts);
+
+            self.notify(
+                "Nova Tentativa Agendada",
+                &format!(
+                    "{}\nTentativa {} de {} em {}s\n{}",
+                    failure.reason(),
+                    attempts + 1,
+                    settings.retry.max_attempts,
+                    delay.as_secs(),
+                    name
+                ),
+            );
+
+            let at = Utc::now() + chrono::Duration::from_std(delay).unwrap_or_default();
+            return ActiveOutcome::Retry(at);
+        }
+
+        self.notify("Erro no Download", &format!("{}\n{}", failure.reason(), name));
+
+        ActiveOutcome::Failed(failure)
+    }
+}
+
+// Roda a tarefa no runtime tokio atual ou, quando chamado de fora dele (menu do tray,
+// monitor da área de transferência), num runtime próprio em outra thread
+fn spawn_task(task: impl Future<Output = ()> + Send + 'static) {
+    match tokio::runtime::Handle::try_current() {
+        Ok(runtime) => {
+            runtime.spawn(task);
+        }
+        Err(_) => {
+            thread::spawn(move || {
+                if let Ok(runtime) = tokio::runtime::Builder::new_current_thread().enable_all().build() {
+                    runtime.block_on(task);
+                }
+            });
+        }
+    }
+}
@@ -0,0 +1,97 @@
+// Download automático de URLs detectadas na área de transferência
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::extractors::url_host;
+use crate::rules::host_matches;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AutoDownloadMode {
+    Off,
+    All,
+    Domains,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoDownloadOptions {
+    pub mode: AutoDownloadMode,
+    // Usado no modo Domains; subdomínios também são aceitos
+    pub domains: Vec<String>,
+    // Janela para desfazer antes de enfileirar
+    pub delay_secs: u64,
+}
+
+impl Default for AutoDownloadOptions {
+    fn default() -> Self {
+        Self {
+            mode: AutoDownloadMode::Off,
+            domains: Vec::new(),
+            delay_secs: 5,
+        }
+    }
+}
+
+impl AutoDownloadOptions {
+    pub fn applies_to(&self, url: &str) -> bool {
+        match self.mode {
+            AutoDownloadMode::Off => false,
+            AutoDownloadMode::All => true,
+            // Mesma regra de domínio das regras de sites: subdomínios inclusos, "*" como curinga
+            AutoDownloadMode::Domains => url_host(url)
+                .is_some_and(|host| self.domains.iter().any(|domain| host_matches(domain, &host))),
+        }
+    }
+}
+
+// URLs aguardando o fim da janela de desfazer
+#[derive(Default)]
+pub struct PendingDownloads {
+    pending: Mutex<Vec<(String, Instant)>>,
+}
+
+impl PendingDownloads {
+    // Agenda a URL; copiar de novo a mesma URL reinicia o prazo
+    pub fn schedule(&self, url: String, delay: Duration) {
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|(u, _)| *u != url);
+        pending.push((url, Instant::now() + delay));
+    }
+
+    pub fn take_due(&self) -> Vec<String> {
+        let now = Instant::now();
+        let mut pending = self.pending.lock().unwrap();
+        let (due, waiting): (Vec<_>, Vec<_>) = pending.drain(..).partition(|(_, at)| *at <= now);
+        *pending = waiting;
+        due.into_iter().map(|(url, _)| url).collect()
+    }
+
+    pub fn cancel_all(&self) -> usize {
+        let mut pending = self.pending.lock().unwrap();
+        let count = pending.len();
+        pending.clear();
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn domains_mode_uses_the_site_rule_matching() {
+        let options = AutoDownloadOptions {
+            mode: AutoDownloadMode::Domains,
+            domains: vec!["youtube.com".to_string(), "*.vimeo.com".to_string(), " ".to_string()],
+            ..AutoDownloadOptions::default()
+        };
+        assert!(options.applies_to("https://www.youtube.com/watch?v=abc"));
+        assert!(options.applies_to("https://YOUTUBE.com./watch?v=abc"));
+        assert!(options.applies_to("https://player.vimeo.com/video/1"));
+        assert!(!options.applies_to("https://notyoutube.com/watch?v=abc"));
+        assert!(!options.applies_to("ftp://youtube.com/video"));
+    }
+}
@@ -0,0 +1,109 @@
+// gallery-dl para sites de imagens: uma linha por arquivo baixado, sem porcentagem
+
+use std::fs;
+use std::path::Path;
+use tokio::process::Command;
+
+use super::{run_process, BoxFuture, DownloadJob, Downloader, Line, Program};
+use crate::app_core::Core;
+use crate::errors::{DownloadFailure, ErrorKind};
+use crate::history::HistoryEntry;
+use crate::queue::DownloadProgress;
+
+pub struct GalleryDl {
+    binary: String,
+}
+
+impl GalleryDl {
+    pub fn new(binary: &str) -> Self {
+        Self {
+            binary: binary.to_string(),
+        }
+    }
+}
+
+impl Downloader for GalleryDl {
+    fn download<'a>(
+        &'a self,
+        core: &'a Core,
+        job: &'a DownloadJob<'a>,
+        entry: &'a mut HistoryEntry,
+    ) -> BoxFuture<'a, Result<(), DownloadFailure>> {
+        let program = Program {
+            name: &self.binary,
+            missing: ErrorKind::BackendMissing,
+            error_message,
+        };
+
+        // --destination mantém as subpastas por site e galeria criadas pelo gallery-dl
+        let mut command = Command::new(&self.binary);
+        command.arg("--destination").arg(&job.output.dir);
+        if let Some(rate) = job.rate_limit {
+            command.arg("--limit-rate").arg(format!("{}k", rate));
+        }
+        command.arg("--").arg(&job.request.url);
+
+        // Sem total conhecido, o progresso soma os bytes dos arquivos já gravados
+        let mut downloaded = 0;
+
+        Box::pin(run_process(core, &job.request.url, program, command, entry, move |line, entry| {
+            let Some(path) = parse_file_line(line) else {
+                return Line::Log;
+            };
+
+            downloaded += fs::metadata(path).map(|metadata| metadata.len()).unwrap_or(0);
+
+            // A pasta da galeria dá nome à entrada do histórico
+            if entry.title.is_none() {
+                entry.title = Path::new(path)
+                    .parent()
+                    .and_then(Path::file_name)
+                    .map(|name| name.to_string_lossy().into_owned());
+            }
+            entry.file_path = Some(path.to_string());
+
+            Line::Progress(DownloadProgress {
+                downloaded_bytes: Some(downloaded),
+                ..DownloadProgress::default()
+            })
+        }))
+    }
+}
+
+// Arquivos baixados saem como caminhos; os já existentes, com "# " na frente.
+// Avisos e erros ("[categoria][nível] ...") e qualquer outra saída não são arquivos
+fn parse_file_line(line: &str) -> Option<&str> {
+    let line = line.trim();
+    let path = line.strip_prefix("# ").unwrap_or(line);
+    Path::new(path).is_file().then_some(path)
+}
+
+// "[gallery-dl][error] mensagem"
+fn error_message(log: &str) -> Option<String> {
+    log.lines()
+        .rev()
+        .find_map(|line| line.split_once("][error] "))
+        .map(|(_, message)| message.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_output() {
+        let dir = std::env::temp_dir().join(format!("ytdl-gallery-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("01.jpg");
+        fs::write(&file, "jpg").unwrap();
+        let path = file.to_string_lossy();
+
+        assert_eq!(parse_file_line(&path), Some(path.as_ref()));
+        assert_eq!(parse_file_line(&format!("# {}", path)), Some(path.as_ref()));
+        assert_eq!(parse_file_line("[imgur][warning] Unable to fetch"), None);
+        let _ = fs::remove_dir_all(&dir);
+
+        let log = "[imgur][warning] retrying\n[gallery-dl][error] HttpError: '404 Not Found'";
+        assert_eq!(error_message(log).as_deref(), Some("HttpError: '404 Not Found'"));
+    }
+}
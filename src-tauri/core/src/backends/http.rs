@@ -0,0 +1,250 @@
+// Download direto por HTTP para links de arquivos de mídia (.mp4, .mp3 etc.),
+// com continuação pelo arquivo .part e o mesmo limite de banda dos outros programas
+
+use percent_encoding::percent_decode_str;
+use reqwest::{header, StatusCode};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::fs::{self, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use url::Url;
+
+use super::{BoxFuture, DownloadJob, Downloader};
+use crate::app_core::Core;
+use crate::errors::{self, DownloadFailure, ErrorKind};
+use crate::history::HistoryEntry;
+use crate::queue::DownloadProgress;
+
+// Intervalo mínimo entre avisos de progresso
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+pub struct HttpDownloader;
+
+impl Downloader for HttpDownloader {
+    fn download<'a>(
+        &'a self,
+        core: &'a Core,
+        job: &'a DownloadJob<'a>,
+        entry: &'a mut HistoryEntry,
+    ) -> BoxFuture<'a, Result<(), DownloadFailure>> {
+        Box::pin(async move {
+            let url = job.request.url.as_str();
+            let kill = core.queue.register_kill_switch(url);
+
+            let result = tokio::select! {
+                result = download_file(core, job, entry) => result,
+                // Parado pela fila: o .part fica para continuar depois
+                _ = kill.notified() => Ok(()),
+            };
+
+            core.queue.remove_kill_switch(url);
+            result
+        })
+    }
+}
+
+async fn download_file(core: &Core, job: &DownloadJob<'_>, entry: &mut HistoryEntry) -> Result<(), DownloadFailure> {
+    let url = job.request.url.as_str();
+    let name = file_name(url);
+    // O .part é de cada URL: links diferentes com o mesmo nome não continuam o arquivo um do outro
+    let part = job.output.dir.join(format!("{}.{:016x}.part", name, url_hash(url)));
+
+    entry.extractor = Some("HTTP".to_string());
+    entry.title = Path::new(&name)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned());
+
+    fs::create_dir_all(&job.output.dir).await.map_err(io_failure)?;
+
+    let resume_from = fs::metadata(&part).await.map(|metadata| metadata.len()).unwrap_or(0);
+    let client = reqwest::Client::builder()
+        .user_agent(concat!("ytdl-monitor/", env!("CARGO_PKG_VERSION")))
+        .connect_timeout(Duration::from_secs(30))
+        .build()
+        .map_err(request_failure)?;
+
+    let mut request = client.get(url);
+    if resume_from > 0 {
+        request = request.header(header::RANGE, format!("bytes={}-", resume_from));
+    }
+
+    let mut response = request.send().await.map_err(request_failure)?;
+    let status = response.status();
+
+    if !status.is_success() {
+        return Err(status_failure(status));
+    }
+
+    // Servidor sem suporte a Range devolve 200 com o arquivo inteiro
+    let resumed = resume_from > 0 && status == StatusCode::PARTIAL_CONTENT;
+    let mut downloaded = if resumed { resume_from } else { 0 };
+    let total_bytes = response.content_length().map(|length| length + downloaded);
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resumed)
+        .truncate(!resumed)
+        .open(&part)
+        .await
+        .map_err(io_failure)?;
+
+    let started = Instant::now();
+    let mut received = 0u64;
+    let mut last_progress = Instant::now() - PROGRESS_INTERVAL;
+
+    while let Some(chunk) = response.chunk().await.map_err(request_failure)? {
+        file.write_all(&chunk).await.map_err(io_failure)?;
+        downloaded += chunk.len() as u64;
+        received += chunk.len() as u64;
+
+        let elapsed = started.elapsed().as_secs_f64();
+
+        // Segura a leitura até a média voltar para o limite (KiB/s)
+        if let Some(rate) = job.rate_limit.filter(|rate| *rate > 0) {
+            let expected = received as f64 / (rate as f64 * 1024.0);
+            if expected > elapsed {
+                tokio::time::sleep(Duration::from_secs_f64(expected - elapsed)).await;
+            }
+        }
+
+        if last_progress.elapsed() >= PROGRESS_INTERVAL {
+            last_progress = Instant::now();
+
+            let speed = (elapsed > 0.0).then(|| received as f64 / elapsed);
+            let progress = DownloadProgress {
+                percent: total_bytes
+                    .filter(|total| *total > 0)
+                    .map(|total| (downloaded as f64 / total as f64 * 100.0).min(100.0)),
+                downloaded_bytes: Some(downloaded),
+                total_bytes,
+                speed,
+                eta: match (total_bytes, speed) {
+                    (Some(total), Some(speed)) if speed > 0.0 => {
+                        Some((total.saturating_sub(downloaded) as f64 / speed) as u64)
+                    }
+                    _ => None,
+                },
+                ..DownloadProgress::default()
+            };
+
+            core.events.progress(job.request.url.as_str(), &progress);
+            core.queue.update_progress(job.request.url.as_str(), progress);
+        }
+    }
+
+    file.flush().await.map_err(io_failure)?;
+    drop(file);
+
+    // Arquivo de mesmo nome já existente (outro link ou download anterior) não é sobrescrito
+    let path = unique_path(&job.output.dir, &name).await;
+    fs::rename(&part, &path).await.map_err(io_failure)?;
+    entry.file_path = Some(path.to_string_lossy().into_owned());
+
+    Ok(())
+}
+
+// Último segmento do caminho, decodificado e sem caracteres inválidos em nomes de arquivo
+fn file_name(url: &str) -> String {
+    let name = Url::parse(url)
+        .ok()
+        .and_then(|parsed| {
+            parsed
+                .path_segments()
+                .and_then(|mut segments| segments.next_back())
+                .map(|segment| percent_decode_str(segment).decode_utf8_lossy().into_owned())
+        })
+        .unwrap_or_default();
+
+    let name: String = name
+        .chars()
+        .map(|c| if "\\/:*?\"<>|".contains(c) || c.is_control() { '_' } else { c })
+        .collect();
+    let name = name.trim().trim_matches('.');
+
+    if name.is_empty() {
+        "download".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+// "video.mp4", "video (1).mp4", "video (2).mp4"... o primeiro que ainda não existe
+async fn unique_path(dir: &Path, name: &str) -> PathBuf {
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, format!(".{}", extension)),
+        _ => (name, String::new()),
+    };
+
+    let mut path = dir.join(name);
+    let mut counter = 1;
+    while fs::try_exists(&path).await.unwrap_or(false) {
+        path = dir.join(format!("{} ({}){}", stem, counter, extension));
+        counter += 1;
+    }
+    path
+}
+
+// FNV-1a: estável entre execuções, para achar o .part de novo depois de pausar
+fn url_hash(url: &str) -> u64 {
+    url.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn status_failure(status: StatusCode) -> DownloadFailure {
+    let message = format!("HTTP Error {}: {}", status.as_u16(), status.canonical_reason().unwrap_or(""));
+    DownloadFailure {
+        kind: errors::classify(&message),
+        message,
+    }
+}
+
+fn request_failure(e: reqwest::Error) -> DownloadFailure {
+    DownloadFailure {
+        kind: if e.is_connect() || e.is_timeout() {
+            ErrorKind::Network
+        } else {
+            errors::classify(&e.to_string())
+        },
+        message: e.to_string(),
+    }
+}
+
+fn io_failure(e: std::io::Error) -> DownloadFailure {
+    DownloadFailure {
+        kind: errors::classify(&e.to_string()),
+        message: e.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_name_from_url() {
+        assert_eq!(file_name("https://cdn.example.com/media/Meu%20V%C3%ADdeo.mp4?token=1"), "Meu Vídeo.mp4");
+        assert_eq!(file_name("https://cdn.example.com/a%2Fb%3F.mp3"), "a_b_.mp3");
+        assert_eq!(file_name("https://cdn.example.com/"), "download");
+    }
+
+    #[tokio::test]
+    async fn never_reuses_an_existing_file_name() {
+        let dir = std::env::temp_dir().join(format!("ytdl-http-names-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        assert_eq!(unique_path(&dir, "video.mp4").await, dir.join("video.mp4"));
+        std::fs::write(dir.join("video.mp4"), "a").unwrap();
+        assert_eq!(unique_path(&dir, "video.mp4").await, dir.join("video (1).mp4"));
+        std::fs::write(dir.join("video (1).mp4"), "b").unwrap();
+        assert_eq!(unique_path(&dir, "video.mp4").await, dir.join("video (2).mp4"));
+
+        std::fs::write(dir.join("download"), "c").unwrap();
+        assert_eq!(unique_path(&dir, "download").await, dir.join("download (1)"));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_ne!(url_hash("https://a.test/a/video.mp4"), url_hash("https://a.test/b/video.mp4"));
+    }
+}
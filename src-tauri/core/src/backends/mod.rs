@@ -0,0 +1,289 @@
+// Programas de download: yt-dlp (ou youtube-dl) para sites de vídeo, gallery-dl para sites
+// de imagens e HTTP direto para links de arquivos de mídia. O programa é escolhido por URL
+
+mod gallery_dl;
+mod http;
+mod ytdlp;
+
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+use tokio::sync::mpsc;
+use url::Url;
+
+use crate::app_core::Core;
+use crate::errors::{self, DownloadFailure, ErrorKind};
+use crate::history::HistoryEntry;
+use crate::output::ResolvedOutput;
+use crate::queue::{DownloadProgress, DownloadRequest};
+use crate::rules;
+use crate::settings::Settings;
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+pub use ytdlp::parse_progress_line;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BackendKind {
+    YtDlp,
+    YoutubeDl,
+    GalleryDl,
+    Http,
+}
+
+impl BackendKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            BackendKind::YtDlp => "yt-dlp",
+            BackendKind::YoutubeDl => "youtube-dl",
+            BackendKind::GalleryDl => "gallery-dl",
+            BackendKind::Http => "HTTP direto",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackendOptions {
+    // Programa dos sites de vídeo: yt-dlp (caminho em Settings::ytdlp_path) ou youtube-dl
+    pub video: BackendKind,
+    pub youtube_dl_path: String,
+    pub gallery_dl_path: String,
+    // Domínios baixados pelo gallery-dl (incluem subdomínios, aceitam "*")
+    pub gallery_hosts: Vec<String>,
+    // Extensões baixadas por HTTP direto, sem passar pelo yt-dlp
+    pub direct_extensions: Vec<String>,
+}
+
+impl Default for BackendOptions {
+    fn default() -> Self {
+        Self {
+            video: BackendKind::YtDlp,
+            youtube_dl_path: "youtube-dl".to_string(),
+            gallery_dl_path: "gallery-dl".to_string(),
+            gallery_hosts: ["imgur.com", "flickr.com", "deviantart.com", "artstation.com", "pixiv.net"]
+                .iter()
+                .map(|host| host.to_string())
+                .collect(),
+            direct_extensions: ["mp4", "webm", "mkv", "mov", "m4v", "avi", "mp3", "m4a", "ogg", "opus", "flac", "wav"]
+                .iter()
+                .map(|ext| ext.to_string())
+                .collect(),
+        }
+    }
+}
+
+// Programa para a URL: o da regra do site, HTTP direto ou gallery-dl quando a URL
+// pede e, nos demais casos, o programa de vídeo configurado
+pub fn detect(settings: &Settings, url: &str) -> BackendKind {
+    rules::allowed_rule(&settings.site_rules, url)
+        .and_then(|rule| rule.backend)
+        .or_else(|| special_backend(&settings.backends, url))
+        .unwrap_or(settings.backends.video)
+}
+
+// Links de arquivos de mídia e sites de imagens, que não dependem do yt-dlp
+pub fn special_backend(options: &BackendOptions, url: &str) -> Option<BackendKind> {
+    let parsed = Url::parse(url.trim()).ok()?;
+    if parsed.scheme() != "http" && parsed.scheme() != "https" {
+        return None;
+    }
+
+    let is_media_file = parsed
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .and_then(|name| name.rsplit_once('.'))
+        .is_some_and(|(_, extension)| {
+            options
+                .direct_extensions
+                .iter()
+                .any(|ext| ext.trim().trim_start_matches('.').eq_ignore_ascii_case(extension))
+        });
+    if is_media_file {
+        return Some(BackendKind::Http);
+    }
+
+    let host = parsed.host_str()?.trim_end_matches('.').to_lowercase();
+    options
+        .gallery_hosts
+        .iter()
+        .any(|pattern| rules::host_matches(pattern, &host))
+        .then_some(BackendKind::GalleryDl)
+}
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+// Pedido com as configurações já resolvidas para o download
+pub struct DownloadJob<'a> {
+    pub request: &'a DownloadRequest,
+    pub settings: &'a Settings,
+    pub output: &'a ResolvedOutput,
+    // KiB/s
+    pub rate_limit: Option<u64>,
+}
+
+pub trait Downloader: Send + Sync {
+    // Baixa o item preenchendo metadados, arquivo e log em `entry`. Quando a fila pede a
+    // parada (kill switch), termina com Ok e quem chamou decide pelo pedido de parada
+    fn download<'a>(
+        &'a self,
+        core: &'a Core,
+        job: &'a DownloadJob<'a>,
+        entry: &'a mut HistoryEntry,
+    ) -> BoxFuture<'a, Result<(), DownloadFailure>>;
+}
+
+pub fn downloader(kind: BackendKind, settings: &Settings) -> Box<dyn Downloader> {
+    match kind {
+        BackendKind::YtDlp => Box::new(ytdlp::YtDlp::new(&settings.ytdlp_path, false)),
+        BackendKind::YoutubeDl => Box::new(ytdlp::YtDlp::new(&settings.backends.youtube_dl_path, true)),
+        BackendKind::GalleryDl => Box::new(gallery_dl::GalleryDl::new(&settings.backends.gallery_dl_path)),
+        BackendKind::Http => Box::new(http::HttpDownloader),
+    }
+}
+
+// Como o executor de processos trata cada linha da saída
+enum Line {
+    Progress(DownloadProgress),
+    // Metadados ou caminho do arquivo, já aplicados à entrada do histórico
+    Handled,
+    Log,
+}
+
+// Programa externo: nome para as mensagens, classe de erro se não existir e como
+// extrair a mensagem de erro do log
+struct Program<'a> {
+    name: &'a str,
+    missing: ErrorKind,
+    error_message: fn(&str) -> Option<String>,
+}
+
+// Executa o programa repassando progresso e metadados; as demais linhas viram o log.
+// O kill switch da fila mata o processo, e a leitura continua até esvaziar as saídas
+async fn run_process(
+    core: &Core,
+    url: &str,
+    program: Program<'_>,
+    mut command: Command,
+    entry: &mut HistoryEntry,
+    mut parse: impl FnMut(&str, &mut HistoryEntry) -> Line,
+) -> Result<(), DownloadFailure> {
+    let queue = &core.queue;
+
+    command.stdout(Stdio::piped()).stderr(Stdio::piped()).kill_on_drop(true);
+
+    #[cfg(target_os = "windows")]
+    command.creation_flags(0x08000000);
+
+    let mut log = Vec::new();
+
+    let output = match command.spawn() {
+        Ok(mut child) => {
+            let (sender, mut receiver) = mpsc::unbounded_channel();
+            if let Some(stdout) = child.stdout.take() {
+                forward_lines(stdout, sender.clone());
+            }
+            if let Some(stderr) = child.stderr.take() {
+                forward_lines(stderr, sender.clone());
+            }
+            drop(sender);
+
+            let kill = queue.register_kill_switch(url);
+            let mut killed = false;
+
+            loop {
+                tokio::select! {
+                    line = receiver.recv() => {
+                        let Some(line) = line else {
+                            break;
+                        };
+
+                        match parse(&line, entry) {
+                            Line::Progress(progress) => {
+                                core.events.progress(url, &progress);
+                                queue.update_progress(url, progress);
+                            }
+                            Line::Handled => {}
+                            Line::Log if !line.trim().is_empty() => {
+                                queue.append_log(url, &line);
+                                log.push(line);
+                            }
+                            Line::Log => {}
+                        }
+                    }
+                    _ = kill.notified(), if !killed => {
+                        killed = true;
+                        let _ = child.start_kill();
+                    }
+                }
+            }
+
+            queue.remove_kill_switch(url);
+            child.wait().await
+        }
+        Err(e) => Err(e),
+    };
+
+    let log = log.join("\n");
+    entry.log = Some(log.clone()).filter(|log| !log.is_empty());
+
+    match output {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(DownloadFailure {
+            kind: errors::classify(&log),
+            message: (program.error_message)(&log)
+                .unwrap_or_else(|| format!("{} terminou com {}", program.name, status)),
+        }),
+        Err(e) => Err(DownloadFailure {
+            kind: if e.kind() == std::io::ErrorKind::NotFound {
+                program.missing
+            } else {
+                ErrorKind::Unknown
+            },
+            message: format!("{}: {}", program.name, e),
+        }),
+    }
+}
+
+// Repassa as linhas de uma saída do processo; a tarefa termina quando o processo fecha a saída
+fn forward_lines<R: AsyncRead + Unpin + Send + 'static>(stream: R, sender: mpsc::UnboundedSender<String>) {
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(stream).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::SiteRule;
+
+    #[test]
+    fn picks_backend_per_url() {
+        let mut settings = Settings::default();
+        assert_eq!(detect(&settings, "https://www.youtube.com/watch?v=abc"), BackendKind::YtDlp);
+        assert_eq!(detect(&settings, "https://cdn.example.com/media/clip.MP4?token=1"), BackendKind::Http);
+        assert_eq!(detect(&settings, "https://imgur.com/gallery/abc"), BackendKind::GalleryDl);
+        assert_eq!(detect(&settings, "https://i.imgur.com/abc.webm"), BackendKind::Http);
+
+        settings.backends.video = BackendKind::YoutubeDl;
+        settings.site_rules.insert(
+            0,
+            SiteRule {
+                backend: Some(BackendKind::GalleryDl),
+                ..SiteRule::allow("twitter.com")
+            },
+        );
+        assert_eq!(detect(&settings, "https://vimeo.com/123"), BackendKind::YoutubeDl);
+        assert_eq!(detect(&settings, "https://twitter.com/a/status/1"), BackendKind::GalleryDl);
+    }
+}
@@ -0,0 +1,336 @@
+// yt-dlp e youtube-dl: argumentos e leitura do progresso e dos metadados
+
+use std::path::Path;
+use tokio::process::Command;
+
+use super::{run_process, BoxFuture, DownloadJob, Downloader, Line, Program};
+use crate::app_core::Core;
+use crate::errors::{self, DownloadFailure, ErrorKind};
+use crate::history::{HistoryEntry, VideoInfo};
+use crate::output;
+use crate::queue::DownloadProgress;
+use crate::settings::FormatProfile;
+
+// Prefixo das linhas de progresso emitidas via --progress-template
+const PROGRESS_PREFIX: &str = "[ytdl-progress]";
+
+// Campos separados por "|": baixado, total, total estimado, velocidade, ETA, fragmento atual, total de fragmentos
+const PROGRESS_TEMPLATE: &str = "download:[ytdl-progress]%(progress.downloaded_bytes)s|%(progress.total_bytes)s|%(progress.total_bytes_estimate)s|%(progress.speed)s|%(progress.eta)s|%(progress.fragment_index)s|%(progress.fragment_count)s";
+
+// Prefixos das linhas de metadados emitidas via --print
+const INFO_PREFIX: &str = "[ytdl-info]";
+const FILEPATH_PREFIX: &str = "[ytdl-file]";
+
+// Apenas os campos usados no histórico, para não imprimir o JSON completo com todos os formatos
+const INFO_TEMPLATE: &str = "before_dl:[ytdl-info]%(.{id,extractor_key,title,uploader,duration})j";
+const FILEPATH_TEMPLATE: &str = "after_move:[ytdl-file]%(filepath)s";
+
+pub struct YtDlp {
+    binary: String,
+    // youtube-dl não tem --print, --progress-template nem -S: o progresso e o arquivo
+    // saem das linhas "[download]" normais
+    youtube_dl: bool,
+}
+
+impl YtDlp {
+    pub fn new(binary: &str, youtube_dl: bool) -> Self {
+        Self {
+            binary: binary.to_string(),
+            youtube_dl,
+        }
+    }
+
+    fn command(&self, job: &DownloadJob<'_>) -> Command {
+        let output = job.output;
+        let profile = job.settings.profile(job.request.profile.as_deref());
+        let mut command = Command::new(&self.binary);
+
+        if self.youtube_dl {
+            let format_args = profile
+                .map(|profile| FormatProfile { sort: None, ..profile.clone() }.args())
+                .unwrap_or_default();
+
+            command.args(format_args).arg("--continue").arg("--newline");
+        } else {
+            // --print implica --quiet; --progress e --no-simulate mantêm o progresso e o download
+            let format_args = profile.map(FormatProfile::args).unwrap_or_default();
+
+            command
+                .args(format_args)
+                .arg("--continue")
+                .arg("--no-simulate")
+                .arg("--progress")
+                .arg("--newline")
+                .arg("--progress-template")
+                .arg(PROGRESS_TEMPLATE)
+                .arg("--print")
+                .arg(INFO_TEMPLATE)
+                .arg("--print")
+                .arg(FILEPATH_TEMPLATE);
+        }
+
+        command
+            .args(output.subtitles.args(self.youtube_dl))
+            .args(output::post_processor_args(&output.post_processors, self.youtube_dl))
+            .arg("-o")
+            .arg(output.template_path());
+
+        if let Some(rate) = job.rate_limit {
+            command.arg("--limit-rate").arg(format!("{}K", rate));
+        }
+
+        // Depois do "--" a URL nunca é lida como opção
+        command.arg("--").arg(&job.request.url);
+
+        command
+    }
+}
+
+impl Downloader for YtDlp {
+    fn download<'a>(
+        &'a self,
+        core: &'a Core,
+        job: &'a DownloadJob<'a>,
+        entry: &'a mut HistoryEntry,
+    ) -> BoxFuture<'a, Result<(), DownloadFailure>> {
+        let program = Program {
+            name: &self.binary,
+            missing: if self.youtube_dl {
+                ErrorKind::BackendMissing
+            } else {
+                ErrorKind::YtdlpMissing
+            },
+            error_message: errors::error_message,
+        };
+        let command = self.command(job);
+        let youtube_dl = self.youtube_dl;
+
+        Box::pin(run_process(core, &job.request.url, program, command, entry, move |line, entry| {
+            if youtube_dl {
+                parse_youtube_dl_line(line, entry)
+            } else {
+                parse_ytdlp_line(line, entry)
+            }
+        }))
+    }
+}
+
+fn parse_ytdlp_line(line: &str, entry: &mut HistoryEntry) -> Line {
+    if let Some(progress) = parse_progress_line(line) {
+        Line::Progress(progress)
+    } else if let Some(info) = parse_info_line(line) {
+        entry.apply_info(info);
+        Line::Handled
+    } else if let Some(path) = parse_filepath_line(line) {
+        entry.file_path = Some(path);
+        Line::Handled
+    } else {
+        Line::Log
+    }
+}
+
+// Sem metadados no youtube-dl: o título vem do nome do arquivo
+fn parse_youtube_dl_line(line: &str, entry: &mut HistoryEntry) -> Line {
+    if let Some(progress) = parse_youtube_dl_progress(line) {
+        return Line::Progress(progress);
+    }
+
+    if let Some(path) = parse_youtube_dl_destination(line) {
+        entry.title = Path::new(&path)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned());
+        entry.file_path = Some(path);
+    }
+    Line::Log
+}
+
+fn parse_info_line(line: &str) -> Option<VideoInfo> {
+    let data = line.trim().strip_prefix(INFO_PREFIX)?;
+    serde_json::from_str(data).ok()
+}
+
+fn parse_filepath_line(line: &str) -> Option<String> {
+    let path = line.trim().strip_prefix(FILEPATH_PREFIX)?.trim();
+    (!path.is_empty() && path != "NA").then(|| path.to_string())
+}
+
+// Converte uma linha do --progress-template em DownloadProgress ("NA" vira None)
+pub fn parse_progress_line(line: &str) -> Option<DownloadProgress> {
+    let data = line.trim().strip_prefix(PROGRESS_PREFIX)?;
+    let fields: Vec<&str> = data.split('|').collect();
+
+    if fields.len() != 7 {
+        return None;
+    }
+
+    let number = |value: &str| value.trim().parse::<f64>().ok().filter(|n| n.is_finite() && *n >= 0.0);
+    let integer = |value: &str| number(value).map(|n| n as u64);
+
+    let downloaded_bytes = integer(fields[0]);
+    let total_bytes = integer(fields[1]).or_else(|| integer(fields[2]));
+    let percent = match (downloaded_bytes, total_bytes) {
+        (Some(done), Some(total)) if total > 0 => Some((done as f64 / total as f64 * 100.0).min(100.0)),
+        _ => None,
+    };
+
+    Some(DownloadProgress {
+        percent,
+        downloaded_bytes,
+        total_bytes,
+        speed: number(fields[3]),
+        eta: integer(fields[4]),
+        fragment_index: integer(fields[5]),
+        fragment_count: integer(fields[6]),
+    })
+}
+
+// "[download]  45.3% of ~12.34MiB at  1.23MiB/s ETA 00:10"
+fn parse_youtube_dl_progress(line: &str) -> Option<DownloadProgress> {
+    let mut words = line.trim().strip_prefix("[download]")?.split_whitespace();
+    let percent = words.next()?.strip_suffix('%')?.parse::<f64>().ok()?.min(100.0);
+
+    let mut progress = DownloadProgress {
+        percent: Some(percent),
+        ..DownloadProgress::default()
+    };
+
+    while let Some(word) = words.next() {
+        match word {
+            "of" => progress.total_bytes = words.next().and_then(parse_size).map(|n| n as u64),
+            "at" => progress.speed = words.next().and_then(|w| w.strip_suffix("/s")).and_then(parse_size),
+            "ETA" => progress.eta = words.next().and_then(parse_duration),
+            _ => {}
+        }
+    }
+    progress.downloaded_bytes = progress
+        .total_bytes
+        .map(|total| (total as f64 * percent / 100.0) as u64);
+
+    Some(progress)
+}
+
+// "12.34MiB" em bytes; "~" marca tamanho estimado
+fn parse_size(text: &str) -> Option<f64> {
+    let text = text.trim_start_matches('~');
+    let split = text.find(|c: char| c.is_ascii_alphabetic())?;
+    let (number, unit) = text.split_at(split);
+    let multiplier = match unit {
+        "B" => 1.0,
+        "KiB" => 1024.0,
+        "MiB" => 1024.0 * 1024.0,
+        "GiB" => 1024.0 * 1024.0 * 1024.0,
+        "TiB" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+    number.parse::<f64>().ok().map(|n| n * multiplier)
+}
+
+// "01:02:03" ou "02:03" em segundos
+fn parse_duration(text: &str) -> Option<u64> {
+    text.split(':')
+        .try_fold(0, |total, part| part.parse::<u64>().ok().map(|n| total * 60 + n))
+}
+
+fn parse_youtube_dl_destination(line: &str) -> Option<String> {
+    let line = line.trim();
+    let path = line
+        .strip_prefix("[download] Destination:")
+        .or_else(|| line.strip_prefix("[ffmpeg] Merging formats into"))
+        .or_else(|| {
+            line.strip_prefix("[download] ")
+                .and_then(|rest| rest.strip_suffix(" has already been downloaded"))
+        })?;
+
+    let path = path.trim().trim_matches('"');
+    (!path.is_empty()).then(|| path.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_progress_with_known_total() {
+        let progress = parse_progress_line("[ytdl-progress]512|1024|NA|256.5|2|NA|NA").unwrap();
+        assert_eq!(progress.downloaded_bytes, Some(512));
+        assert_eq!(progress.total_bytes, Some(1024));
+        assert_eq!(progress.percent, Some(50.0));
+        assert_eq!(progress.speed, Some(256.5));
+        assert_eq!(progress.eta, Some(2));
+        assert_eq!(progress.fragment_index, None);
+    }
+
+    #[test]
+    fn falls_back_to_estimated_total() {
+        let progress = parse_progress_line("[ytdl-progress]300|NA|1200.0|NA|NA|3|10").unwrap();
+        assert_eq!(progress.total_bytes, Some(1200));
+        assert_eq!(progress.percent, Some(25.0));
+        assert_eq!(progress.fragment_index, Some(3));
+        assert_eq!(progress.fragment_count, Some(10));
+    }
+
+    #[test]
+    fn rejects_other_lines() {
+        assert!(parse_progress_line("[download]  50.0% of 10MiB").is_none());
+        assert!(parse_progress_line("[ytdl-progress]1|2|3").is_none());
+    }
+
+    #[test]
+    fn parses_info_and_filepath_lines() {
+        let info = parse_info_line(r#"[ytdl-info]{"id": "abc", "extractor_key": "Youtube", "title": "Vídeo", "uploader": null, "duration": 61.0}"#)
+            .unwrap();
+        assert_eq!(info.id.as_deref(), Some("abc"));
+        assert_eq!(info.extractor_key.as_deref(), Some("Youtube"));
+        assert_eq!(info.duration, Some(61.0));
+
+        assert_eq!(parse_filepath_line("[ytdl-file]/tmp/a.mp4").as_deref(), Some("/tmp/a.mp4"));
+        assert_eq!(parse_filepath_line("[ytdl-file]NA"), None);
+    }
+
+    #[test]
+    fn parses_youtube_dl_output() {
+        let progress = parse_youtube_dl_progress("[download]  50.0% of ~2.00MiB at  1.00KiB/s ETA 01:05").unwrap();
+        assert_eq!(progress.percent, Some(50.0));
+        assert_eq!(progress.total_bytes, Some(2 * 1024 * 1024));
+        assert_eq!(progress.downloaded_bytes, Some(1024 * 1024));
+        assert_eq!(progress.speed, Some(1024.0));
+        assert_eq!(progress.eta, Some(65));
+
+        let progress = parse_youtube_dl_progress("[download] 100% of 10.00KiB in 00:01").unwrap();
+        assert_eq!(progress.percent, Some(100.0));
+        assert!(parse_youtube_dl_progress("[download] Destination: a.mp4").is_none());
+
+        assert_eq!(
+            parse_youtube_dl_destination("[download] Destination: /tmp/Vídeo.f137.mp4").as_deref(),
+            Some("/tmp/Vídeo.f137.mp4")
+        );
+        assert_eq!(
+            parse_youtube_dl_destination("[ffmpeg] Merging formats into \"/tmp/Vídeo.mkv\"").as_deref(),
+            Some("/tmp/Vídeo.mkv")
+        );
+        assert_eq!(
+            parse_youtube_dl_destination("[download] /tmp/Vídeo.mp4 has already been downloaded").as_deref(),
+            Some("/tmp/Vídeo.mp4")
+        );
+    }
+
+    #[test]
+    fn url_always_comes_after_the_options() {
+        let settings = crate::settings::Settings::default();
+        let request = crate::queue::DownloadRequest::new("--exec=touch /tmp/x".to_string(), None);
+        let output = output::OutputOptions::default().resolve(&settings);
+        let job = DownloadJob {
+            request: &request,
+            settings: &settings,
+            output: &output,
+            rate_limit: Some(100),
+        };
+
+        for youtube_dl in [false, true] {
+            let command = YtDlp::new("yt-dlp", youtube_dl).command(&job);
+            let args: Vec<_> = command.as_std().get_args().map(|arg| arg.to_string_lossy().into_owned()).collect();
+            assert_eq!(args[args.len() - 2..], ["--", "--exec=touch /tmp/x"]);
+            assert!(args.iter().position(|arg| arg == "--limit-rate") < args.iter().position(|arg| arg == "--"));
+        }
+    }
+}
@@ -0,0 +1,154 @@
+// Limite de banda global e agenda por horário
+
+use chrono::{NaiveTime, Timelike};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const SECS_PER_DAY: u32 = 24 * 3600;
+
+// Faixa de horário "HH:MM"–"HH:MM"; pode atravessar a meia-noite (ex.: 22:00–06:00)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeRange {
+    pub start: String,
+    pub end: String,
+}
+
+impl TimeRange {
+    fn bounds(&self) -> Option<(u32, u32)> {
+        let parse = |s: &str| NaiveTime::parse_from_str(s.trim(), "%H:%M").ok();
+        Some((
+            parse(&self.start)?.num_seconds_from_midnight(),
+            parse(&self.end)?.num_seconds_from_midnight(),
+        ))
+    }
+
+    // Faixa inválida ou vazia (início = fim) nunca contém nenhum horário
+    pub fn contains(&self, now: NaiveTime) -> bool {
+        let Some((start, end)) = self.bounds() else {
+            return false;
+        };
+        let now = now.num_seconds_from_midnight();
+        if start <= end {
+            start <= now && now < end
+        } else {
+            now >= start || now < end
+        }
+    }
+}
+
+// Limite próprio para uma faixa de horário
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpeedWindow {
+    #[serde(flatten)]
+    pub range: TimeRange,
+    // KiB/s; 0 = sem limite nessa faixa
+    pub limit_kib: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BandwidthOptions {
+    // Limite total em KiB/s dividido entre os downloads ativos; 0 = sem limite
+    pub limit_kib: u64,
+    // A primeira faixa que contiver o horário atual substitui o limite global
+    pub schedule: Vec<SpeedWindow>,
+    // Se definido, downloads só começam (e só continuam) dentro dessa faixa
+    pub active_hours: Option<TimeRange>,
+}
+
+impl BandwidthOptions {
+    pub fn downloads_allowed(&self, now: NaiveTime) -> bool {
+        self.active_hours.as_ref().is_none_or(|range| range.contains(now))
+    }
+
+    pub fn current_limit(&self, now: NaiveTime) -> u64 {
+        self.schedule
+            .iter()
+            .find(|window| window.range.contains(now))
+            .map_or(self.limit_kib, |window| window.limit_kib)
+    }
+
+    // Parte do limite de cada download ativo; None = sem limite
+    pub fn share(&self, now: NaiveTime, active: usize) -> Option<u64> {
+        let limit = self.current_limit(now);
+        if limit == 0 {
+            return None;
+        }
+        Some((limit / active.max(1) as u64).max(1))
+    }
+
+    // Tempo até o próximo início ou fim de faixa, para o agendador reavaliar limites e horários
+    pub fn next_change_in(&self, now: NaiveTime) -> Option<Duration> {
+        let now = now.num_seconds_from_midnight();
+        self.schedule
+            .iter()
+            .map(|window| &window.range)
+            .chain(self.active_hours.as_ref())
+            .filter_map(TimeRange::bounds)
+            .flat_map(|(start, end)| [start, end])
+            .map(|boundary| (boundary + SECS_PER_DAY - now - 1) % SECS_PER_DAY + 1)
+            .min()
+            .map(|secs| Duration::from_secs(secs as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn range(start: &str, end: &str) -> TimeRange {
+        TimeRange {
+            start: start.to_string(),
+            end: end.to_string(),
+        }
+    }
+
+    #[test]
+    fn active_hours_can_cross_midnight() {
+        let night = BandwidthOptions {
+            active_hours: Some(range("22:00", "06:00")),
+            ..BandwidthOptions::default()
+        };
+        assert!(night.downloads_allowed(at(23, 30)));
+        assert!(night.downloads_allowed(at(0, 0)));
+        assert!(night.downloads_allowed(at(5, 59)));
+        assert!(!night.downloads_allowed(at(6, 0)));
+        assert!(!night.downloads_allowed(at(12, 0)));
+        assert!(night.downloads_allowed(at(22, 0)));
+
+        let day = BandwidthOptions {
+            active_hours: Some(range("08:00", "18:00")),
+            ..BandwidthOptions::default()
+        };
+        assert!(day.downloads_allowed(at(8, 0)));
+        assert!(!day.downloads_allowed(at(18, 0)));
+        assert!(!day.downloads_allowed(at(23, 0)));
+
+        // Sem faixa tudo é permitido; faixa inválida ou vazia não permite nada
+        assert!(BandwidthOptions::default().downloads_allowed(at(3, 0)));
+        assert!(!range("25:00", "06:00").contains(at(3, 0)));
+        assert!(!range("10:00", "10:00").contains(at(10, 0)));
+    }
+
+    #[test]
+    fn schedule_and_share_follow_the_clock() {
+        let options = BandwidthOptions {
+            limit_kib: 1000,
+            schedule: vec![SpeedWindow {
+                range: range("23:00", "01:00"),
+                limit_kib: 0,
+            }],
+            active_hours: None,
+        };
+        assert_eq!(options.share(at(12, 0), 4), Some(250));
+        assert_eq!(options.share(at(12, 0), 0), Some(1000));
+        assert_eq!(options.share(at(0, 30), 4), None);
+
+        assert_eq!(options.next_change_in(at(22, 0)), Some(Duration::from_secs(3600)));
+        assert_eq!(options.next_change_in(at(0, 30)), Some(Duration::from_secs(1800)));
+    }
+}
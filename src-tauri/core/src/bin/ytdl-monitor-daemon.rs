@@ -0,0 +1,108 @@
+// Modo sem interface: fila, agendador, socket local e API HTTP, com as notificações
+// escritas no stderr. Para servidores e sessões SSH; depende só do núcleo e do tokio,
+// sem as bibliotecas gráficas do app. Com argumentos, funciona como a CLI do `ytdl-monitor`
+
+use chrono::Local;
+use std::sync::Arc;
+use std::time::Duration;
+
+use ytdl_monitor_core::api::ApiServer;
+use ytdl_monitor_core::queue::{DownloadProgress, QuitMode};
+use ytdl_monitor_core::{cli, ipc};
+use ytdl_monitor_core::{Core, DownloadStatus, EventSink, Settings};
+
+#[derive(Default)]
+struct DaemonSink {
+    api: ApiServer,
+}
+
+impl EventSink for DaemonSink {
+    fn notify(&self, title: &str, body: &str) {
+        eprintln!("[{}] {}: {}", Local::now().format("%H:%M:%S"), title, body.replace('\n', " | "));
+    }
+
+    fn status_changed(&self, _status: &DownloadStatus) {}
+
+    fn progress(&self, _url: &str, _progress: &DownloadProgress) {}
+
+    fn settings_changed(&self, core: &Arc<Core>, settings: &Settings) {
+        self.api.apply(core, &settings.api);
+    }
+}
+
+// SIGINT ou SIGTERM (Ctrl+C no Windows)
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+            }
+            Err(_) => {
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let code = match args.first().map(String::as_str) {
+        None | Some("daemon") => run(),
+        Some(command) => cli::run(&args).unwrap_or_else(|| {
+            eprintln!("Comando desconhecido: {}. Use --help para ver os comandos.", command);
+            2
+        }),
+    };
+    std::process::exit(code);
+}
+
+#[tokio::main]
+async fn run() -> i32 {
+    if ipc::instance_running() {
+        eprintln!("O ytdl-monitor já está em execução; use os subcomandos para controlá-lo.");
+        return 1;
+    }
+
+    let events = Arc::new(DaemonSink::default());
+    let core = Core::new(events.clone());
+
+    events.api.apply(&core, &core.settings.get().api);
+    core.notify_restored();
+    tokio::spawn(core.clone().run_scheduler());
+
+    eprintln!("ytdl-monitor em execução sem interface; Ctrl+C para sair");
+
+    let result = tokio::select! {
+        result = ipc::serve_local(core.clone()) => result,
+        _ = shutdown_signal() => Ok(()),
+    };
+
+    // Downloads em andamento voltam à fila e continuam dos arquivos .part na próxima execução
+    core.queue.begin_shutdown(QuitMode::Requeue);
+    while core.queue.active_count() > 0 {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+    // O socket de outra instância que começou junto não é nosso para apagar
+    if !matches!(&result, Err(e) if e.kind() == std::io::ErrorKind::AddrInUse) {
+        ipc::remove_socket();
+    }
+
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("Erro no socket local: {}", e);
+            1
+        }
+    }
+}
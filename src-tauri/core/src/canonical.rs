@@ -0,0 +1,272 @@
+// Identidade dos vídeos para detectar duplicatas: "extrator:id" (como o extractor_key
+// e o id do yt-dlp) ou, para sites desconhecidos, a URL normalizada com prefixo "url:"
+
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+use url::Url;
+
+use crate::rules::host_matches;
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+// Rastreamento que não muda a página em nenhum site (além dos "utm_*")
+const TRACKING_PARAMS: &[&str] = &["fbclid", "gclid"];
+
+// Nos sites de vídeo conhecidos também não mudam o vídeo: rastreamento, compartilhamento e
+// posição inicial. Em outros sites "s", "t", "ref" ou "start" podem identificar o conteúdo
+const VIDEO_SITE_PARAMS: &[&str] = &[
+    "dclid", "msclkid", "mc_cid", "mc_eid", "igshid", "igsh", "si", "feature", "ref", "ref_src", "ref_url", "s",
+    "t", "start", "time_continue", "pp", "ab_channel", "share_id",
+];
+
+const VIDEO_HOSTS: &[&str] = &[
+    "youtube.com",
+    "youtu.be",
+    "youtube-nocookie.com",
+    "vimeo.com",
+    "dailymotion.com",
+    "dai.ly",
+    "tiktok.com",
+    "twitter.com",
+    "x.com",
+    "instagram.com",
+    "twitch.tv",
+];
+
+// Tempo máximo da consulta ao yt-dlp; sem resposta, o download segue sem a chave resolvida
+const QUERY_TIMEOUT: Duration = Duration::from_secs(30);
+
+fn is_ignored_param(name: &str, video_site: bool) -> bool {
+    let name = name.to_lowercase();
+    name.starts_with("utm_")
+        || TRACKING_PARAMS.contains(&name.as_str())
+        || (video_site && VIDEO_SITE_PARAMS.contains(&name.as_str()))
+}
+
+// Host sem "www.", "m." e "mobile.", para casar as versões de celular
+fn base_host(parsed: &Url) -> Option<String> {
+    let mut host = parsed.host_str()?.trim_end_matches('.').to_lowercase();
+    for prefix in ["www.", "m.", "mobile."] {
+        if let Some(rest) = host.strip_prefix(prefix) {
+            host = rest.to_string();
+        }
+    }
+    Some(host)
+}
+
+// https, host sem prefixo móvel, caminho sem "/" final, sem fragmento nem parâmetros ignorados
+pub fn normalize_url(url: &str) -> String {
+    let url = url.trim();
+    let Ok(parsed) = Url::parse(url) else {
+        return url.to_string();
+    };
+    let Some(host) = base_host(&parsed) else {
+        return url.to_string();
+    };
+
+    let video_site = VIDEO_HOSTS.iter().any(|domain| host_matches(domain, &host));
+    let params: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(name, _)| !is_ignored_param(name, video_site))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+
+    let mut normalized = format!("https://{}{}", host, parsed.path().trim_end_matches('/'));
+    if !params.is_empty() {
+        let query: String = url::form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(params)
+            .finish();
+        normalized.push('?');
+        normalized.push_str(&query);
+    }
+    normalized
+}
+
+// Chave conhecida sem consultar o yt-dlp, para os sites com formato de URL estável
+fn known_key(parsed: &Url) -> Option<String> {
+    let host = base_host(parsed)?;
+    let segments: Vec<&str> = parsed
+        .path_segments()
+        .map(|segments| segments.filter(|s| !s.is_empty()).collect())
+        .unwrap_or_default();
+    let param = |name: &str| {
+        parsed
+            .query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    };
+    let is = |domain: &str| host_matches(domain, &host);
+
+    let (extractor, id) = if host == "youtu.be" {
+        ("youtube", segments.first().map(|id| id.to_string()))
+    } else if is("youtube.com") || is("youtube-nocookie.com") {
+        let id = match segments.as_slice() {
+            ["watch", ..] => param("v"),
+            ["shorts" | "embed" | "live" | "v" | "e", id, ..] => Some(id.to_string()),
+            _ => None,
+        };
+        ("youtube", id)
+    } else if is("vimeo.com") {
+        // vimeo.com/123, player.vimeo.com/video/123, vimeo.com/channels/x/123
+        let id = segments.iter().rev().find(|s| s.chars().all(|c| c.is_ascii_digit()));
+        ("vimeo", id.map(|id| id.to_string()))
+    } else if host == "dai.ly" {
+        ("dailymotion", segments.first().map(|id| id.to_string()))
+    } else if is("dailymotion.com") {
+        // /video/x8abc12_titulo-do-video
+        let id = match segments.as_slice() {
+            ["video", slug, ..] => slug.split('_').next().map(str::to_string),
+            _ => None,
+        };
+        ("dailymotion", id)
+    } else if is("tiktok.com") {
+        let id = match segments.as_slice() {
+            [_, "video", id, ..] => Some(id.to_string()),
+            _ => None,
+        };
+        ("tiktok", id)
+    } else if is("twitter.com") || is("x.com") {
+        let id = match segments.as_slice() {
+            [_, "status", id, ..] | ["i", "web", "status", id, ..] => Some(id.to_string()),
+            _ => None,
+        };
+        ("twitter", id)
+    } else if is("instagram.com") {
+        let id = match segments.as_slice() {
+            ["p" | "reel" | "reels" | "tv", id, ..] => Some(id.to_string()),
+            _ => None,
+        };
+        ("instagram", id)
+    } else if is("twitch.tv") {
+        let id = match segments.as_slice() {
+            ["videos", id, ..] => Some(format!("v{}", id)),
+            _ => None,
+        };
+        ("twitchvod", id)
+    } else {
+        return None;
+    };
+
+    let id = id.filter(|id| !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-'))?;
+    Some(format!("{}:{}", extractor, id))
+}
+
+// Chave da URL sem acesso à rede: "youtube:dQw4w9WgXcQ" ou "url:https://..."
+pub fn video_key(url: &str) -> String {
+    Url::parse(url.trim())
+        .ok()
+        .and_then(|parsed| known_key(&parsed))
+        .unwrap_or_else(|| format!("url:{}", normalize_url(url)))
+}
+
+// Falso para chaves "url:", que só o yt-dlp consegue resolver em extrator + id
+pub fn is_resolved(key: &str) -> bool {
+    !key.starts_with("url:")
+}
+
+// Chave a partir dos metadados do yt-dlp (extractor_key e id)
+pub fn info_key(extractor: Option<&str>, id: Option<&str>) -> Option<String> {
+    match (extractor, id) {
+        (Some(extractor), Some(id)) if !extractor.is_empty() && !id.is_empty() => {
+            Some(format!("{}:{}", extractor.to_lowercase(), id))
+        }
+        _ => None,
+    }
+}
+
+// Pergunta ao yt-dlp o extrator e o id do vídeo, sem baixar. None se não conseguir
+pub async fn query_key(ytdlp: &str, url: &str) -> Option<String> {
+    let mut command = Command::new(ytdlp);
+    command
+        .arg("--simulate")
+        .arg("--no-playlist")
+        .arg("--no-warnings")
+        .arg("--print")
+        .arg("%(extractor_key)s\t%(id)s")
+        .arg("--")
+        .arg(url)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true);
+
+    #[cfg(target_os = "windows")]
+    command.creation_flags(0x08000000);
+
+    // Travado (rede, site lento): o kill_on_drop encerra o processo ao estourar o tempo
+    let output = tokio::time::timeout(QUERY_TIMEOUT, command.output()).await.ok()?.ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (extractor, id) = stdout.lines().next()?.trim().split_once('\t')?;
+    info_key(Some(extractor), Some(id).filter(|id| *id != "NA"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn youtube_variants_share_a_key() {
+        let key = "youtube:dQw4w9WgXcQ";
+        for url in [
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            "https://youtube.com/watch?v=dQw4w9WgXcQ&t=30s&list=PL123",
+            "https://m.youtube.com/watch?feature=share&v=dQw4w9WgXcQ",
+            "https://youtu.be/dQw4w9WgXcQ?si=abc123",
+            "https://www.youtube.com/shorts/dQw4w9WgXcQ",
+            "https://music.youtube.com/watch?v=dQw4w9WgXcQ",
+            "https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ?start=10",
+        ] {
+            assert_eq!(video_key(url), key, "{}", url);
+        }
+    }
+
+    #[test]
+    fn known_sites() {
+        assert_eq!(video_key("https://vimeo.com/76979871"), "vimeo:76979871");
+        assert_eq!(video_key("https://player.vimeo.com/video/76979871?h=1"), "vimeo:76979871");
+        assert_eq!(video_key("https://www.dailymotion.com/video/x7tgad0_titulo"), "dailymotion:x7tgad0");
+        assert_eq!(video_key("https://dai.ly/x7tgad0"), "dailymotion:x7tgad0");
+        assert_eq!(video_key("https://x.com/user/status/123?s=20"), "twitter:123");
+        assert_eq!(video_key("https://twitter.com/user/status/123/video/1"), "twitter:123");
+        assert_eq!(video_key("https://www.instagram.com/reel/Cabc_1/?igshid=x"), "instagram:Cabc_1");
+        assert_eq!(video_key("https://www.tiktok.com/@user/video/7012345"), "tiktok:7012345");
+        assert_eq!(video_key("https://www.twitch.tv/videos/987"), "twitchvod:v987");
+    }
+
+    #[test]
+    fn unknown_sites_use_the_normalized_url() {
+        assert_eq!(
+            video_key("http://www.example.com/v/abc/?utm_source=x&id=2&fbclid=y#t=30"),
+            "url:https://example.com/v/abc?id=2"
+        );
+        assert_eq!(video_key("https://m.example.com/v/abc"), video_key("https://example.com/v/abc/"));
+        assert!(!is_resolved(&video_key("https://example.com/v/abc")));
+        assert!(is_resolved(&video_key("https://youtu.be/dQw4w9WgXcQ")));
+        // Canal ou página sem id de vídeo
+        assert!(!is_resolved(&video_key("https://www.youtube.com/@canal")));
+    }
+
+    #[test]
+    fn share_params_are_only_dropped_on_video_sites() {
+        assert_eq!(
+            video_key("https://www.youtube.com/@canal/videos?si=abc&feature=share&view=0"),
+            "url:https://youtube.com/@canal/videos?view=0"
+        );
+        // Em outros sites "s", "t", "start" e "ref" podem fazer parte do endereço do conteúdo
+        assert_eq!(
+            video_key("https://example.com/busca?s=rust&t=2&start=20&ref=main&gclid=x&utm_medium=y"),
+            "url:https://example.com/busca?s=rust&t=2&start=20&ref=main"
+        );
+    }
+
+    #[test]
+    fn info_key_matches_known_key() {
+        assert_eq!(info_key(Some("Youtube"), Some("dQw4w9WgXcQ")).as_deref(), Some("youtube:dQw4w9WgXcQ"));
+        assert_eq!(info_key(Some("Youtube"), None), None);
+    }
+}
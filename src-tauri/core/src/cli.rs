@@ -0,0 +1,214 @@
+// Subcomandos da linha de comando, comuns ao `ytdl-monitor` e ao `ytdl-monitor-daemon`:
+// falam com a instância em execução (tray ou daemon) pelo socket local
+
+use chrono::{DateTime, Local, Utc};
+use serde_json::{json, Value};
+
+use crate::ipc;
+
+const USAGE: &str = "Uso: ytdl-monitor [comando]
+
+Sem comando, abre o monitor na bandeja do sistema. Para rodar sem interface
+(servidores, SSH), use o programa ytdl-monitor-daemon, que aceita os mesmos comandos.
+
+Comandos:
+  add <url> [--profile <id>]   adiciona um download à fila
+  list                         mostra a fila
+  cancel <url>                 cancela um download ou o remove da fila
+  resume <url>                 retoma um download pausado
+  retry <url>                  tenta de novo um download que falhou
+  history [busca]              mostra o histórico
+  config get [chave]           mostra as configurações (ex.: bandwidth.limit_kib)
+  config set <chave> <valor>   altera uma configuração (valor em JSON ou texto)";
+
+// None quando não há subcomando: o chamador abre a interface do tray
+pub fn run(args: &[String]) -> Option<i32> {
+    let command = args.first()?.as_str();
+    let args = &args[1..];
+
+    let result = match command {
+        // O modo sem interface não depende das bibliotecas gráficas e é um programa à parte
+        "daemon" => Err("O modo sem interface agora é o programa ytdl-monitor-daemon.".to_string()),
+        "add" => add(args),
+        "list" => list(),
+        "cancel" => cancel(args),
+        "resume" => resume(args),
+        "retry" => retry(args),
+        "history" => history(args),
+        "config" => config(args),
+        "help" | "-h" | "--help" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        // Argumentos que o sistema passa ao abrir o app (ex.: -psn_ no macOS)
+        _ => return None,
+    };
+
+    Some(match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    })
+}
+
+// Requisição à instância em execução; erros HTTP viram a mensagem do servidor
+fn call(method: &str, path: &str, body: Option<Value>) -> Result<(u16, Value), String> {
+    let (status, value) = ipc::request(method, path, body.as_ref()).map_err(|e| {
+        format!(
+            "Não foi possível falar com o ytdl-monitor ({}).\nAbra o app na bandeja ou rode `ytdl-monitor-daemon`.",
+            e
+        )
+    })?;
+
+    if status >= 400 && status != 409 {
+        return Err(value["error"].as_str().unwrap_or("erro desconhecido").to_string());
+    }
+    Ok((status, value))
+}
+
+fn usage_error(message: &str) -> String {
+    format!("{}\n\n{}", message, USAGE)
+}
+
+fn add(args: &[String]) -> Result<(), String> {
+    let mut url = None;
+    let mut profile = None;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--profile" | "-p" => {
+                profile = Some(args.next().ok_or_else(|| usage_error("Faltou o perfil depois de --profile"))?);
+            }
+            _ => url = Some(arg),
+        }
+    }
+
+    let url = url.ok_or_else(|| usage_error("Informe a URL"))?;
+    let (status, value) = call("POST", "/downloads", Some(json!({ "url": url, "profile": profile })))?;
+
+    let message = match value["result"].as_str().unwrap_or_default() {
+        "queued" => "Adicionado à fila",
+        "expanding" => "Expandindo a playlist; os vídeos entram na fila em seguida",
+        "already_queued" => "Já está na fila",
+        "already_downloaded" => "Já foi baixado anteriormente",
+        "no_url" => "URL vazia",
+        "invalid_url" => "URL inválida",
+        _ => "Resposta desconhecida",
+    };
+
+    if status == 409 {
+        return Err(message.to_string());
+    }
+    println!("{}", message);
+    Ok(())
+}
+
+fn list() -> Result<(), String> {
+    let (_, status) = call("GET", "/downloads", None)?;
+    let count = |key: &str| status[key].as_u64().unwrap_or(0);
+
+    println!(
+        "{} ativo(s), {} na fila, {} pausado(s), {} com falha",
+        count("active"),
+        count("queued"),
+        count("paused"),
+        count("failed")
+    );
+
+    for item in status["downloads"].as_array().into_iter().flatten() {
+        let state = match item["status"].as_str().unwrap_or_default() {
+            "active" => "baixando",
+            "queued" => "na fila",
+            "paused" => "pausado",
+            "failed" => "falhou",
+            other => other,
+        };
+        let percent = item["progress"]["percent"]
+            .as_f64()
+            .map(|percent| format!("{:.1}%", percent))
+            .unwrap_or_default();
+        let url = item["url"].as_str().unwrap_or_default();
+
+        match item["title"].as_str() {
+            Some(title) => println!("{:<9} {:>6}  {} ({})", state, percent, title, url),
+            None => println!("{:<9} {:>6}  {}", state, percent, url),
+        }
+        if let Some(error) = item["error"].as_str() {
+            println!("{:18}{}", "", error);
+        }
+    }
+    Ok(())
+}
+
+fn cancel(args: &[String]) -> Result<(), String> {
+    let url = args.first().ok_or_else(|| usage_error("Informe a URL"))?;
+    call("POST", "/downloads/cancel", Some(json!({ "url": url })))?;
+    println!("Cancelado");
+    Ok(())
+}
+
+fn resume(args: &[String]) -> Result<(), String> {
+    let url = args.first().ok_or_else(|| usage_error("Informe a URL"))?;
+    call("POST", "/downloads/resume", Some(json!({ "url": url })))?;
+    println!("Retomado");
+    Ok(())
+}
+
+fn retry(args: &[String]) -> Result<(), String> {
+    let url = args.first().ok_or_else(|| usage_error("Informe a URL"))?;
+    call("POST", "/downloads/retry", Some(json!({ "url": url })))?;
+    println!("De volta à fila");
+    Ok(())
+}
+
+fn history(args: &[String]) -> Result<(), String> {
+    let query: String = url::form_urlencoded::byte_serialize(args.join(" ").as_bytes()).collect();
+    let (_, entries) = call("GET", &format!("/history?q={}", query), None)?;
+
+    for entry in entries.as_array().into_iter().flatten() {
+        let date = entry["finished_at"]
+            .as_str()
+            .or(entry["started_at"].as_str())
+            .and_then(|date| date.parse::<DateTime<Utc>>().ok())
+            .map(|date| date.with_timezone(&Local).format("%d/%m/%Y %H:%M").to_string())
+            .unwrap_or_default();
+        let state = match entry["status"].as_str().unwrap_or_default() {
+            "completed" => "concluído",
+            "failed" => "falhou",
+            "cancelled" => "cancelado",
+            other => other,
+        };
+        let name = entry["title"].as_str().or(entry["url"].as_str()).unwrap_or_default();
+
+        println!("{:<16}  {:<9}  {}", date, state, name);
+    }
+    Ok(())
+}
+
+fn config(args: &[String]) -> Result<(), String> {
+    match args.first().map(String::as_str) {
+        Some("get") => {
+            let path = match args.get(1) {
+                Some(key) => format!("/config?key={}", url::form_urlencoded::byte_serialize(key.as_bytes()).collect::<String>()),
+                None => "/config".to_string(),
+            };
+            let (_, value) = call("GET", &path, None)?;
+            println!("{}", serde_json::to_string_pretty(&value).unwrap_or_default());
+            Ok(())
+        }
+        Some("set") => {
+            let (Some(key), Some(raw)) = (args.get(1), args.get(2)) else {
+                return Err(usage_error("Uso: config set <chave> <valor>"));
+            };
+            // Números, booleanos, listas e objetos em JSON; o resto vale como texto
+            let value = serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.clone()));
+            call("POST", "/config", Some(json!({ "key": key, "value": value })))?;
+            println!("Configuração salva");
+            Ok(())
+        }
+        _ => Err(usage_error("Use config get ou config set")),
+    }
+}
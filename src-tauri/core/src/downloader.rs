@@ -0,0 +1,77 @@
+// Execução de um download pelo programa escolhido para a URL e registro no histórico
+
+use crate::app_core::Core;
+use crate::backends::{self, BackendKind, DownloadJob};
+use crate::canonical;
+use crate::errors::DownloadFailure;
+use crate::history::{HistoryEntry, HistoryStatus};
+use crate::queue::{DownloadRequest, StopReason};
+use crate::settings::Settings;
+
+// Função para baixar o vídeo com o yt-dlp ou outro programa configurado
+pub async fn download_video(
+    core: &Core,
+    request: &DownloadRequest,
+    settings: &Settings,
+    rate_limit: Option<u64>,
+) -> Result<(), DownloadFailure> {
+    let url = request.url.as_str();
+    let queue = &core.queue;
+    let history = &core.history;
+
+    let output = request.output.resolve(settings);
+    let job = DownloadJob {
+        request,
+        settings,
+        output: &output,
+        rate_limit,
+    };
+    let kind = backends::detect(settings, url);
+
+    // Sites sem formato de URL conhecido: o yt-dlp diz qual é o vídeo antes de baixar
+    if settings.check_duplicates
+        && kind == BackendKind::YtDlp
+        && !canonical::is_resolved(&canonical::video_key(url))
+    {
+        if let Some(key) = canonical::query_key(&settings.ytdlp_path, url).await {
+            if history.contains_key(&key) {
+                core.notify("Vídeo Já Baixado", "Este vídeo já foi baixado anteriormente");
+                return Ok(());
+            }
+        }
+    }
+
+    let backend = backends::downloader(kind, settings);
+    let mut entry = HistoryEntry::new(url);
+    let result = backend.download(core, &job, &mut entry).await;
+
+    // Interrompido pelo usuário (cancelar/pausar): o processador da fila trata pelo pedido de parada
+    if let Some(reason) = queue.stop_request(url) {
+        if reason == StopReason::Cancel {
+            entry.finish(HistoryStatus::Cancelled, None);
+            history.add(entry);
+        }
+        return Ok(());
+    }
+
+    match result {
+        Ok(()) => {
+            entry.finish(HistoryStatus::Completed, None);
+            history.add(entry);
+            
+            core.notify("Download Concluído", "Vídeo baixado com sucesso!");
+            
+            if settings.enable_sound {
+                core.events.completion_sound();
+            }
+            
+            Ok(())
+        }
+        Err(failure) => {
+            entry.error_kind = Some(failure.kind);
+            entry.finish(HistoryStatus::Failed, Some(failure.message.clone()));
+            history.add(entry);
+            Err(failure)
+        }
+    }
+}
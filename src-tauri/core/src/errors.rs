@@ -0,0 +1,169 @@
+// Classificação das falhas do yt-dlp a partir da saída capturada
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    GeoBlocked,
+    PrivateVideo,
+    LoginRequired,
+    AgeRestricted,
+    Forbidden,
+    RateLimited,
+    FormatUnavailable,
+    FfmpegMissing,
+    DiskFull,
+    Network,
+    Fragment,
+    YtdlpMissing,
+    // youtube-dl ou gallery-dl não encontrado
+    BackendMissing,
+    Unknown,
+}
+
+impl ErrorKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            ErrorKind::GeoBlocked => "Vídeo bloqueado na sua região",
+            ErrorKind::PrivateVideo => "Vídeo privado",
+            ErrorKind::LoginRequired => "O site exige login (use cookies)",
+            ErrorKind::AgeRestricted => "Vídeo com restrição de idade",
+            ErrorKind::Forbidden => "Acesso negado pelo servidor (HTTP 403)",
+            ErrorKind::RateLimited => "Muitas requisições (HTTP 429)",
+            ErrorKind::FormatUnavailable => "Formato solicitado indisponível",
+            ErrorKind::FfmpegMissing => "ffmpeg não encontrado",
+            ErrorKind::DiskFull => "Sem espaço em disco",
+            ErrorKind::Network => "Falha de conexão",
+            ErrorKind::Fragment => "Falha ao baixar fragmentos do vídeo",
+            ErrorKind::YtdlpMissing => "yt-dlp não encontrado. Verifique a instalação.",
+            ErrorKind::BackendMissing => "Programa de download não encontrado. Verifique a instalação.",
+            ErrorKind::Unknown => "Não foi possível baixar o vídeo",
+        }
+    }
+}
+
+// Ordem importa: problemas locais primeiro, e "sign in to confirm your age" antes do login genérico
+const PATTERNS: &[(ErrorKind, &[&str])] = &[
+    (ErrorKind::DiskFull, &["no space left on device", "errno 28", "not enough space on the disk"]),
+    (
+        ErrorKind::FfmpegMissing,
+        &["ffmpeg is not installed", "ffmpeg not found", "ffprobe and ffmpeg not found", "ffmpeg could not be found"],
+    ),
+    (
+        ErrorKind::GeoBlocked,
+        &["not available in your country", "geo restrict", "geo-restrict", "not available from your location"],
+    ),
+    (ErrorKind::PrivateVideo, &["private video", "video is private"]),
+    (
+        ErrorKind::AgeRestricted,
+        &["confirm your age", "age-restricted", "age restricted", "inappropriate for some users"],
+    ),
+    (
+        ErrorKind::LoginRequired,
+        &["login required", "sign in to confirm", "use --cookies", "requires authentication", "members-only", "join this channel"],
+    ),
+    (ErrorKind::RateLimited, &["http error 429", "too many requests"]),
+    (ErrorKind::Forbidden, &["http error 403", "403: forbidden"]),
+    (ErrorKind::FormatUnavailable, &["requested format is not available", "requested format not available"]),
+    (
+        ErrorKind::Fragment,
+        &[
+            "fragment not found",
+            "not found, unable to continue",
+            "fragment retries",
+            "unable to download fragment",
+            "did not get any data blocks",
+        ],
+    ),
+    (
+        ErrorKind::Network,
+        &[
+            "connection reset",
+            "connection refused",
+            "connection aborted",
+            "timed out",
+            "temporary failure in name resolution",
+            "network is unreachable",
+            "remote end closed connection",
+            "incompleteread",
+        ],
+    ),
+];
+
+// Falha de um download já classificada
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadFailure {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl DownloadFailure {
+    // Motivo para mostrar ao usuário: o rótulo da classe ou, se desconhecida, a mensagem do yt-dlp
+    pub fn reason(&self) -> String {
+        match self.kind {
+            ErrorKind::Unknown => self.message.clone(),
+            kind => kind.label().to_string(),
+        }
+    }
+}
+
+// Só as linhas "ERROR:" decidem a classe: o progresso e os avisos ("[download] Got fragment 3",
+// "Retrying fragment") não são a causa da falha. Sem nenhuma, vale o log inteiro
+pub fn classify(log: &str) -> ErrorKind {
+    let errors: Vec<&str> = log
+        .lines()
+        .filter(|line| line.trim_start().starts_with("ERROR:"))
+        .collect();
+    let text = if errors.is_empty() {
+        log.to_lowercase()
+    } else {
+        errors.join("\n").to_lowercase()
+    };
+
+    PATTERNS
+        .iter()
+        .find(|(_, needles)| needles.iter().any(|needle| text.contains(needle)))
+        .map(|(kind, _)| *kind)
+        .unwrap_or(ErrorKind::Unknown)
+}
+
+// Última linha "ERROR:" do log, sem o prefixo
+pub fn error_message(log: &str) -> Option<String> {
+    log.lines()
+        .rev()
+        .find_map(|line| line.trim().strip_prefix("ERROR:"))
+        .map(|message| message.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_common_failures() {
+        assert_eq!(classify("ERROR: [youtube] abc: Private video. Sign in if you've been granted access"), ErrorKind::PrivateVideo);
+        assert_eq!(classify("ERROR: unable to download video data: HTTP Error 429: Too Many Requests"), ErrorKind::RateLimited);
+        assert_eq!(classify("ERROR: Sign in to confirm your age. This video may be inappropriate"), ErrorKind::AgeRestricted);
+        assert_eq!(classify("ERROR: something unexpected"), ErrorKind::Unknown);
+        assert_eq!(classify("ERROR: fragment 12 not found, unable to continue"), ErrorKind::Fragment);
+        assert_eq!(classify("ERROR: Giving up after 10 fragment retries"), ErrorKind::Fragment);
+        assert_eq!(classify("Connection reset by peer"), ErrorKind::Network);
+    }
+
+    #[test]
+    fn classifies_by_error_lines_before_progress() {
+        let log = "[hlsnative] Total fragments: 120\n\
+                   [download]   2.5% of ~50.00MiB at 1.20MiB/s (frag 3/120)\n\
+                   [download] Got fragment 4, retrying fragment 5 (attempt 1 of 10)...\n\
+                   ERROR: unable to download video data: HTTP Error 403: Forbidden";
+        assert_eq!(classify(log), ErrorKind::Forbidden);
+    }
+
+    #[test]
+    fn error_message_is_the_last_error_line() {
+        let log = "WARNING: retrying\nERROR: first\nERROR: second";
+        assert_eq!(error_message(log).as_deref(), Some("second"));
+        assert_eq!(error_message("WARNING: only a warning"), None);
+    }
+}
@@ -0,0 +1,215 @@
+// Detecção de URLs suportadas consultando o próprio yt-dlp
+
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+// A consulta acessa o site; sem resposta nesse prazo a URL fica sem decisão
+const QUERY_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Detection {
+    Supported(String),
+    Unsupported,
+}
+
+// URL suportada vale para o host todo: a consulta ao yt-dlp envolve rede, então é feita
+// uma vez por TTL. A recusa vale só para a URL consultada, porque o mesmo site costuma ter
+// páginas sem vídeo e páginas com vídeo
+#[derive(Default)]
+pub struct ExtractorCache {
+    // Host para Supported, URL completa para Unsupported
+    entries: Mutex<HashMap<String, (Detection, Instant)>>,
+    // Hosts com consulta em andamento e as URLs copiadas enquanto isso
+    in_flight: Mutex<HashMap<String, Vec<String>>>,
+}
+
+impl ExtractorCache {
+    pub fn lookup(&self, host: &str, url: &str, ttl: Duration) -> Option<Detection> {
+        let entries = self.entries.lock().unwrap();
+        [host, url]
+            .iter()
+            .filter_map(|key| entries.get(*key))
+            .find(|(_, checked_at)| checked_at.elapsed() < ttl)
+            .map(|(detection, _)| detection.clone())
+    }
+
+    pub fn store(&self, host: &str, url: &str, detection: Detection) {
+        let key = match detection {
+            Detection::Supported(_) => host,
+            Detection::Unsupported => url,
+        };
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), (detection, Instant::now()));
+    }
+
+    // Consulta o yt-dlp, uma vez por host de cada vez, e guarda o resultado. URLs do host
+    // que chegam durante a consulta esperam por ela e são verificadas em seguida. Devolve
+    // as URLs suportadas com o extrator; vazio se outra consulta já cuida da URL
+    pub async fn query(&self, ytdlp: &str, host: &str, url: &str) -> Vec<(String, String)> {
+        {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            if let Some(waiting) = in_flight.get_mut(host) {
+                if !waiting.iter().any(|waiting| waiting == url) {
+                    waiting.push(url.to_string());
+                }
+                return Vec::new();
+            }
+            in_flight.insert(host.to_string(), Vec::new());
+        }
+        let guard = InFlight { cache: self, host };
+
+        let mut supported = Vec::new();
+        let mut next = Some(url.to_string());
+        while let Some(url) = next {
+            let detection = query_extractor(ytdlp, &url).await;
+            if let Some(detection) = &detection {
+                self.store(host, &url, detection.clone());
+            }
+
+            let mut in_flight = self.in_flight.lock().unwrap();
+            let waiting = in_flight.entry(host.to_string()).or_default();
+            next = match detection {
+                // O host está liberado: as URLs à espera também
+                Some(Detection::Supported(extractor)) => {
+                    supported.extend(waiting.drain(..).map(|waiting| (waiting, extractor.clone())));
+                    supported.push((url, extractor));
+                    None
+                }
+                _ if waiting.is_empty() => None,
+                _ => Some(waiting.remove(0)),
+            };
+            // Libera o host sob a mesma trava em que as URLs à espera foram lidas
+            if next.is_none() {
+                in_flight.remove(host);
+            }
+        }
+        // O host já foi liberado; o guard só cobre erros e tarefas canceladas no meio
+        std::mem::forget(guard);
+        supported
+    }
+}
+
+// Libera o host se a consulta termina no meio: erro, tempo esgotado ou tarefa cancelada
+struct InFlight<'a> {
+    cache: &'a ExtractorCache,
+    host: &'a str,
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.cache.in_flight.lock().unwrap().remove(self.host);
+    }
+}
+
+pub fn url_host(url: &str) -> Option<String> {
+    let parsed = url::Url::parse(url.trim()).ok()?;
+    if parsed.scheme() != "http" && parsed.scheme() != "https" {
+        return None;
+    }
+    parsed.host_str().map(|host| host.trim_end_matches('.').to_lowercase())
+}
+
+// Pergunta ao yt-dlp qual extrator atende a URL, sem o extrator genérico.
+// None quando não foi possível decidir (yt-dlp ausente, erro de rede etc.)
+pub async fn query_extractor(ytdlp: &str, url: &str) -> Option<Detection> {
+    let mut command = Command::new(ytdlp);
+    command
+        .arg("--simulate")
+        .arg("--no-playlist")
+        .arg("--no-warnings")
+        .arg("--ies")
+        .arg("default,-generic")
+        .arg("--print")
+        .arg("extractor_key")
+        .arg("--")
+        .arg(url)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    #[cfg(target_os = "windows")]
+    command.creation_flags(0x08000000);
+
+    let output = tokio::time::timeout(QUERY_TIMEOUT, command.output()).await.ok()?.ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    if let Some(extractor) = stdout.lines().map(str::trim).find(|l| !l.is_empty()) {
+        return Some(Detection::Supported(extractor.to_string()));
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.contains("Unsupported URL") {
+        return Some(Detection::Unsupported);
+    }
+
+    // "ERROR: [youtube] abc: Private video" — o extrator reconheceu a URL mas falhou
+    stderr
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("ERROR: ["))
+        .find_map(|rest| rest.split_once(']').map(|(name, _)| name.to_string()))
+        .map(Detection::Supported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn failed_queries_release_the_host() {
+        let cache = ExtractorCache::default();
+        let missing = "/nonexistent/yt-dlp";
+
+        assert_eq!(cache.query(missing, "example.com", "https://example.com/v").await, vec![]);
+        assert!(cache.in_flight.lock().unwrap().is_empty());
+
+        // Consulta em andamento para o mesmo host: a segunda espera por ela
+        cache.in_flight.lock().unwrap().insert("example.com".to_string(), Vec::new());
+        assert_eq!(cache.query(missing, "example.com", "https://example.com/v").await, vec![]);
+        assert_eq!(cache.in_flight.lock().unwrap()["example.com"], ["https://example.com/v"]);
+    }
+
+    // yt-dlp falso: só as URLs com "video" têm extrator
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn urls_copied_during_a_query_are_checked_after_it() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("ytdl-monitor-extractors-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("yt-dlp");
+        std::fs::write(
+            &script,
+            "#!/bin/sh\nfor arg; do url=$arg; done\nsleep 0.2\ncase \"$url\" in\n    *video*) echo Youtube ;;\n    *) echo \"ERROR: Unsupported URL: $url\" >&2; exit 1 ;;\nesac\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let ytdlp = script.to_string_lossy();
+
+        let cache = ExtractorCache::default();
+        let (first, second) = tokio::join!(cache.query(&ytdlp, "site.test", "https://site.test/about"), async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            cache.query(&ytdlp, "site.test", "https://site.test/video/1").await
+        });
+        assert_eq!(first, [("https://site.test/video/1".to_string(), "Youtube".to_string())]);
+        assert_eq!(second, vec![]);
+        assert!(cache.in_flight.lock().unwrap().is_empty());
+
+        // A recusa vale só para a URL; o host ficou liberado pela outra
+        let ttl = Duration::from_secs(3600);
+        assert_eq!(cache.lookup("site.test", "https://site.test/video/2", ttl), Some(Detection::Supported("Youtube".to_string())));
+        let other = ExtractorCache::default();
+        other.store("site.test", "https://site.test/about", Detection::Unsupported);
+        assert_eq!(other.lookup("site.test", "https://site.test/about", ttl), Some(Detection::Unsupported));
+        assert_eq!(other.lookup("site.test", "https://site.test/video/2", ttl), None);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,271 @@
+// Histórico de downloads, salvo em history.json
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::canonical;
+use crate::errors::ErrorKind;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryStatus {
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub id: u64,
+    pub url: String,
+    pub video_id: Option<String>,
+    pub extractor: Option<String>,
+    pub title: Option<String>,
+    pub uploader: Option<String>,
+    pub duration: Option<f64>,
+    pub file_path: Option<String>,
+    pub file_size: Option<u64>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub status: HistoryStatus,
+    pub error: Option<String>,
+    pub error_kind: Option<ErrorKind>,
+    // Saída do yt-dlp (avisos e erros), sem as linhas de progresso
+    pub log: Option<String>,
+}
+
+impl HistoryEntry {
+    pub fn new(url: &str) -> Self {
+        Self {
+            id: 0,
+            url: url.to_string(),
+            video_id: None,
+            extractor: None,
+            title: None,
+            uploader: None,
+            duration: None,
+            file_path: None,
+            file_size: None,
+            started_at: Utc::now(),
+            finished_at: None,
+            status: HistoryStatus::Failed,
+            error: None,
+            error_kind: None,
+            log: None,
+        }
+    }
+
+    pub fn apply_info(&mut self, info: VideoInfo) {
+        self.video_id = info.id;
+        self.extractor = info.extractor_key;
+        self.title = info.title;
+        self.uploader = info.uploader;
+        self.duration = info.duration;
+    }
+
+    // Mesmo vídeo pelos metadados do yt-dlp ou pela URL normalizada
+    pub fn is_video(&self, key: &str) -> bool {
+        canonical::info_key(self.extractor.as_deref(), self.video_id.as_deref()).as_deref() == Some(key)
+            || canonical::video_key(&self.url) == key
+    }
+
+    pub fn finish(&mut self, status: HistoryStatus, error: Option<String>) {
+        self.status = status;
+        self.error = error;
+        self.finished_at = Some(Utc::now());
+        self.file_size = self
+            .file_path
+            .as_ref()
+            .and_then(|path| fs::metadata(path).ok())
+            .map(|meta| meta.len());
+    }
+
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        [
+            Some(&self.url),
+            self.title.as_ref(),
+            self.uploader.as_ref(),
+            self.extractor.as_ref(),
+            self.video_id.as_ref(),
+            self.file_path.as_ref(),
+        ]
+        .iter()
+        .flatten()
+        .any(|field| field.to_lowercase().contains(&query))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadHistory {
+    pub entries: Vec<HistoryEntry>,
+    pub next_id: u64,
+    pub last_updated: DateTime<Utc>,
+}
+
+// Formato antigo do history.json: apenas um conjunto de URLs
+#[derive(Debug, Deserialize)]
+struct LegacyDownloadHistory {
+    urls: HashSet<String>,
+    last_updated: DateTime<Utc>,
+}
+
+impl Default for DownloadHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DownloadHistory {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            next_id: 1,
+            last_updated: Utc::now(),
+        }
+    }
+
+    // Converte o histórico antigo em registros concluídos, sem metadados
+    fn from_legacy(legacy: LegacyDownloadHistory) -> Self {
+        let mut history = Self::new();
+        for url in legacy.urls {
+            let mut entry = HistoryEntry::new(&url);
+            entry.started_at = legacy.last_updated;
+            entry.finished_at = Some(legacy.last_updated);
+            entry.status = HistoryStatus::Completed;
+            history.add(entry);
+        }
+        history.last_updated = legacy.last_updated;
+        history
+    }
+
+    pub fn add(&mut self, mut entry: HistoryEntry) {
+        entry.id = self.next_id;
+        self.next_id += 1;
+        self.entries.push(entry);
+        self.last_updated = Utc::now();
+    }
+
+    pub fn remove(&mut self, id: u64) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|e| e.id != id);
+        self.last_updated = Utc::now();
+        self.entries.len() != before
+    }
+
+    // Vídeo já baixado com sucesso, pela chave de canonical::video_key
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.entries
+            .iter()
+            .any(|e| e.status == HistoryStatus::Completed && e.is_video(key))
+    }
+}
+
+// Metadados impressos pelo yt-dlp antes do download
+#[derive(Debug, Deserialize)]
+pub struct VideoInfo {
+    pub id: Option<String>,
+    pub extractor_key: Option<String>,
+    pub title: Option<String>,
+    pub uploader: Option<String>,
+    pub duration: Option<f64>,
+}
+
+pub struct HistoryManager {
+    history: Arc<Mutex<DownloadHistory>>,
+    history_path: PathBuf,
+}
+
+impl HistoryManager {
+    pub fn open(config_dir: &Path) -> Self {
+        let history_path = config_dir.join("history.json");
+        let history = Self::load_from_file(&history_path);
+        
+        Self {
+            history: Arc::new(Mutex::new(history)),
+            history_path,
+        }
+    }
+
+    fn load_from_file(path: &PathBuf) -> DownloadHistory {
+        let Ok(content) = fs::read_to_string(path) else {
+            return DownloadHistory::new();
+        };
+
+        if let Ok(history) = serde_json::from_str::<DownloadHistory>(&content) {
+            return history;
+        }
+
+        match serde_json::from_str::<LegacyDownloadHistory>(&content) {
+            Ok(legacy) => {
+                let history = DownloadHistory::from_legacy(legacy);
+                if let Ok(json) = serde_json::to_string_pretty(&history) {
+                    fs::write(path, json).ok();
+                }
+                history
+            }
+            Err(_) => DownloadHistory::new(),
+        }
+    }
+
+    fn save(&self) {
+        let history = self.history.lock().unwrap();
+        if let Ok(json) = serde_json::to_string_pretty(&*history) {
+            fs::write(&self.history_path, json).ok();
+        }
+    }
+
+    pub fn add(&self, entry: HistoryEntry) {
+        self.history.lock().unwrap().add(entry);
+        self.save();
+    }
+
+    // Compara pelo vídeo, não pela URL: youtu.be/X e youtube.com/watch?v=X&t=30 são o mesmo
+    pub fn contains(&self, url: &str) -> bool {
+        self.contains_key(&canonical::video_key(url))
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.history.lock().unwrap().contains_key(key)
+    }
+
+    // Registros mais recentes primeiro
+    pub fn entries(&self) -> Vec<HistoryEntry> {
+        let history = self.history.lock().unwrap();
+        history.entries.iter().rev().cloned().collect()
+    }
+
+    // Log da tentativa mais recente de baixar a URL
+    pub fn latest_log(&self, url: &str) -> Option<String> {
+        let history = self.history.lock().unwrap();
+        history
+            .entries
+            .iter()
+            .rev()
+            .find(|entry| entry.url == url)
+            .and_then(|entry| entry.log.clone())
+    }
+
+    pub fn search(&self, query: &str) -> Vec<HistoryEntry> {
+        let history = self.history.lock().unwrap();
+        history
+            .entries
+            .iter()
+            .rev()
+            .filter(|e| e.matches(query))
+            .cloned()
+            .collect()
+    }
+
+    pub fn delete(&self, id: u64) -> bool {
+        let removed = self.history.lock().unwrap().remove(id);
+        if removed {
+            self.save();
+        }
+        removed
+    }
+}
@@ -0,0 +1,120 @@
+// Socket local entre a CLI e a instância em execução (tray ou daemon)
+//
+// Unix: socket de domínio em $XDG_RUNTIME_DIR (ou na pasta de configuração), acessível só ao usuário.
+// Windows: 127.0.0.1 na porta LOCAL_PORT, com o mesmo token da API local.
+// As requisições seguem o formato HTTP/JSON da API local (api.rs).
+
+use serde_json::Value;
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+
+use crate::api;
+use crate::app_core::Core;
+use crate::settings::app_config_dir;
+
+#[cfg(unix)]
+use std::path::PathBuf;
+
+#[cfg(windows)]
+use crate::settings::SettingsManager;
+
+#[cfg(windows)]
+const LOCAL_PORT: u16 = 9469;
+
+#[cfg(unix)]
+pub fn socket_path() -> PathBuf {
+    dirs::runtime_dir()
+        .unwrap_or_else(app_config_dir)
+        .join("ytdl-monitor.sock")
+}
+
+#[cfg(unix)]
+fn connect() -> io::Result<std::os::unix::net::UnixStream> {
+    std::os::unix::net::UnixStream::connect(socket_path())
+}
+
+#[cfg(windows)]
+fn connect() -> io::Result<std::net::TcpStream> {
+    std::net::TcpStream::connect(("127.0.0.1", LOCAL_PORT))
+}
+
+// Outra instância já atende o socket
+pub fn instance_running() -> bool {
+    connect().is_ok()
+}
+
+// Atende a CLI até o processo terminar
+#[cfg(unix)]
+pub async fn serve_local(core: Arc<Core>) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = socket_path();
+    if instance_running() {
+        return Err(io::Error::new(io::ErrorKind::AddrInUse, "outra instância já está em execução"));
+    }
+    // Socket de uma execução anterior que não saiu direito
+    let _ = std::fs::remove_file(&path);
+
+    let listener = tokio::net::UnixListener::bind(&path)?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        tokio::spawn(api::serve_connection(core.clone(), None, stream));
+    }
+}
+
+#[cfg(windows)]
+pub async fn serve_local(core: Arc<Core>) -> io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(("127.0.0.1", LOCAL_PORT)).await?;
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let token = core.settings.get().api.token.clone();
+        tokio::spawn(api::serve_connection(core.clone(), Some(token), stream));
+    }
+}
+
+#[cfg(unix)]
+pub fn remove_socket() {
+    let _ = std::fs::remove_file(socket_path());
+}
+
+#[cfg(windows)]
+pub fn remove_socket() {}
+
+// Envia uma requisição à instância em execução; devolve o código HTTP e o corpo JSON
+pub fn request(method: &str, path: &str, body: Option<&Value>) -> io::Result<(u16, Value)> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "resposta inválida");
+    let body = body.map(Value::to_string).unwrap_or_default();
+
+    let mut head = format!(
+        "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\n",
+        method,
+        path,
+        body.len()
+    );
+    #[cfg(windows)]
+    head.push_str(&format!(
+        "Authorization: Bearer {}\r\n",
+        SettingsManager::open(&app_config_dir()).get().api.token
+    ));
+    head.push_str("\r\n");
+
+    let mut stream = connect()?;
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(body.as_bytes())?;
+
+    // O servidor fecha a conexão depois de cada resposta
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+
+    let (head, body) = response.split_once("\r\n\r\n").ok_or_else(invalid)?;
+    let status = head
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(invalid)?;
+    let value = serde_json::from_str(body).map_err(|_| invalid())?;
+    Ok((status, value))
+}
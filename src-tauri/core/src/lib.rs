@@ -0,0 +1,31 @@
+// Núcleo do YT-DLP Monitor: configurações, histórico, fila, agendador e execução dos downloads.
+// Não depende de janelas nem do Tauri; o tray, o daemon (src/bin) e a CLI são clientes desta biblioteca
+
+pub mod api;
+pub mod app_core;
+pub mod auto_download;
+pub mod backends;
+pub mod bandwidth;
+pub mod canonical;
+pub mod cli;
+pub mod downloader;
+pub mod errors;
+pub mod extractors;
+pub mod history;
+pub mod ipc;
+pub mod limits;
+pub mod output;
+pub mod playlist;
+pub mod queue;
+pub mod recent_urls;
+pub mod retry;
+pub mod rules;
+pub mod settings;
+pub mod validation;
+
+pub use app_core::{Core, EventSink, QueueOutcome};
+pub use backends::BackendKind;
+pub use history::{DownloadHistory, HistoryEntry, HistoryManager, HistoryStatus};
+pub use queue::{DownloadQueue, DownloadRequest, DownloadStatus};
+pub use rules::is_video_url;
+pub use settings::{Settings, SettingsManager};
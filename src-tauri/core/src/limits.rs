@@ -0,0 +1,91 @@
+// Limites por site: downloads simultâneos e intervalo mínimo entre inícios
+
+use crate::extractors::url_host;
+use crate::rules::host_matches;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostLimit {
+    // Mesmo formato das regras de sites: domínio (inclui subdomínios) ou curinga "*"
+    pub host: String,
+    // 0 = sem limite próprio (vale só o max_concurrent global)
+    #[serde(default)]
+    pub max_concurrent: usize,
+    #[serde(default)]
+    pub min_delay_secs: u64,
+}
+
+pub fn default_limits() -> Vec<HostLimit> {
+    vec![HostLimit {
+        host: "tiktok.com".to_string(),
+        max_concurrent: 1,
+        min_delay_secs: 5,
+    }]
+}
+
+// Primeiro limite que casa com o host da URL
+pub fn find_limit<'a>(limits: &'a [HostLimit], url: &str) -> Option<&'a HostLimit> {
+    let host = url_host(url)?;
+    limits.iter().find(|limit| host_matches(&limit.host, &host))
+}
+
+// Último início por limite; a chave é o padrão de host do limite, para que
+// subdomínios do mesmo site dividam a mesma cota
+#[derive(Default)]
+pub struct HostStarts {
+    starts: HashMap<String, Instant>,
+}
+
+impl HostStarts {
+    pub fn record(&mut self, limit: &HostLimit) {
+        self.starts.insert(limit.host.clone(), Instant::now());
+    }
+
+    // Quanto falta para o intervalo mínimo do limite terminar
+    pub fn remaining_delay(&self, limit: &HostLimit) -> Option<Duration> {
+        let started = self.starts.get(&limit.host)?;
+        Duration::from_secs(limit.min_delay_secs)
+            .checked_sub(started.elapsed())
+            .filter(|remaining| !remaining.is_zero())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limit(host: &str, max_concurrent: usize, min_delay_secs: u64) -> HostLimit {
+        HostLimit {
+            host: host.to_string(),
+            max_concurrent,
+            min_delay_secs,
+        }
+    }
+
+    #[test]
+    fn finds_the_first_limit_for_the_host() {
+        let limits = [limit("tiktok.com", 1, 5), limit("*.example.com", 2, 0), limit("*", 3, 0)];
+        let host_of = |url| find_limit(&limits, url).map(|l| l.host.as_str());
+
+        assert_eq!(host_of("https://www.tiktok.com/@user/video/1"), Some("tiktok.com"));
+        assert_eq!(host_of("https://cdn.example.com/v"), Some("*.example.com"));
+        assert_eq!(host_of("https://other.test/v"), Some("*"));
+        assert_eq!(find_limit(&limits[..2], "https://other.test/v").map(|l| l.max_concurrent), None);
+        assert!(find_limit(&limits, "não é url").is_none());
+    }
+
+    #[test]
+    fn delay_counts_from_the_last_start() {
+        let mut starts = HostStarts::default();
+        let polite = limit("tiktok.com", 1, 60);
+        let eager = limit("example.com", 1, 0);
+
+        assert_eq!(starts.remaining_delay(&polite), None);
+        starts.record(&polite);
+        starts.record(&eager);
+        assert!(starts.remaining_delay(&polite).is_some_and(|d| d > Duration::from_secs(59)));
+        assert_eq!(starts.remaining_delay(&eager), None);
+    }
+}
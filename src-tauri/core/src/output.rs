@@ -0,0 +1,230 @@
+// Saída de cada download: pasta, modelo do nome do arquivo, legendas e pós-processamento.
+// O pedido pode trazer valores próprios; o que faltar vem da regra do site e das configurações
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::settings::Settings;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubtitleOptions {
+    // Legendas enviadas pelo autor (--write-subs)
+    pub enabled: bool,
+    // Legendas geradas automaticamente (--write-auto-subs)
+    pub auto_generated: bool,
+    // Códigos ou regex do yt-dlp (ex.: "pt.*", "en"); vazio usa o padrão do yt-dlp
+    pub languages: Vec<String>,
+    // Embutidas no vídeo em vez de arquivos separados
+    pub embed: bool,
+    // Conversão, ex.: "srt"
+    pub format: Option<String>,
+}
+
+impl SubtitleOptions {
+    fn wanted(&self) -> bool {
+        self.enabled || self.auto_generated
+    }
+
+    pub fn args(&self, youtube_dl: bool) -> Vec<String> {
+        let mut args = Vec::new();
+        if !self.wanted() {
+            return args;
+        }
+
+        // youtube-dl usa os nomes antigos das opções
+        let (subs, auto_subs, langs) = if youtube_dl {
+            ("--write-sub", "--write-auto-sub", "--sub-lang")
+        } else {
+            ("--write-subs", "--write-auto-subs", "--sub-langs")
+        };
+
+        if self.enabled {
+            args.push(subs.to_string());
+        }
+        if self.auto_generated {
+            args.push(auto_subs.to_string());
+        }
+
+        let languages: Vec<&str> = self
+            .languages
+            .iter()
+            .map(|lang| lang.trim())
+            .filter(|lang| !lang.is_empty())
+            .collect();
+        if !languages.is_empty() {
+            args.push(langs.to_string());
+            args.push(languages.join(","));
+        }
+
+        if let Some(format) = self.format.as_deref().map(str::trim).filter(|f| !f.is_empty()) {
+            args.push("--convert-subs".to_string());
+            args.push(format.to_string());
+        }
+        if self.embed {
+            args.push("--embed-subs".to_string());
+        }
+
+        args
+    }
+}
+
+// Etapas executadas pelo yt-dlp depois do download
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PostProcessor {
+    EmbedThumbnail,
+    EmbedMetadata,
+    EmbedChapters,
+    // Remove patrocínios marcados no SponsorBlock
+    SponsorblockRemove,
+    // Um arquivo por capítulo
+    SplitChapters,
+}
+
+impl PostProcessor {
+    // None quando o youtube-dl não tem a etapa
+    fn args(self, youtube_dl: bool) -> Option<&'static [&'static str]> {
+        Some(match (self, youtube_dl) {
+            (PostProcessor::EmbedThumbnail, _) => &["--embed-thumbnail"],
+            (PostProcessor::EmbedMetadata, false) => &["--embed-metadata"],
+            (PostProcessor::EmbedMetadata, true) => &["--add-metadata"],
+            (PostProcessor::EmbedChapters, false) => &["--embed-chapters"],
+            (PostProcessor::SponsorblockRemove, false) => &["--sponsorblock-remove", "default"],
+            (PostProcessor::SplitChapters, false) => &["--split-chapters"],
+            (_, true) => return None,
+        })
+    }
+}
+
+pub fn post_processor_args(post_processors: &[PostProcessor], youtube_dl: bool) -> Vec<String> {
+    post_processors
+        .iter()
+        .filter_map(|pp| pp.args(youtube_dl))
+        .flatten()
+        .map(|arg| arg.to_string())
+        .collect()
+}
+
+// Valores próprios de um pedido ou de uma regra de site; None segue o nível de cima
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputOptions {
+    pub output_dir: Option<String>,
+    // Modelo do yt-dlp relativo à pasta, ex.: "%(uploader)s/%(title)s.%(ext)s"
+    pub filename_template: Option<String>,
+    pub subtitles: Option<SubtitleOptions>,
+    pub post_processors: Option<Vec<PostProcessor>>,
+}
+
+impl OutputOptions {
+    // Completa os campos vazios com os de `defaults` (a regra do site)
+    pub fn or(self, defaults: &OutputOptions) -> OutputOptions {
+        OutputOptions {
+            output_dir: non_empty(self.output_dir).or_else(|| non_empty(defaults.output_dir.clone())),
+            filename_template: non_empty(self.filename_template)
+                .or_else(|| non_empty(defaults.filename_template.clone())),
+            subtitles: self.subtitles.or_else(|| defaults.subtitles.clone()),
+            post_processors: self.post_processors.or_else(|| defaults.post_processors.clone()),
+        }
+    }
+
+    // Valores finais, com as configurações globais no lugar do que faltar
+    pub fn resolve(&self, settings: &Settings) -> ResolvedOutput {
+        let output_dir = non_empty(self.output_dir.clone()).unwrap_or_else(|| settings.download_path.clone());
+        let filename_template =
+            non_empty(self.filename_template.clone()).unwrap_or_else(|| settings.filename_template.clone());
+
+        ResolvedOutput {
+            dir: PathBuf::from(output_dir.trim()),
+            filename_template,
+            subtitles: self.subtitles.clone().unwrap_or_else(|| settings.subtitles.clone()),
+            post_processors: self
+                .post_processors
+                .clone()
+                .unwrap_or_else(|| settings.post_processors.clone()),
+        }
+    }
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|value| !value.trim().is_empty())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedOutput {
+    pub dir: PathBuf,
+    pub filename_template: String,
+    pub subtitles: SubtitleOptions,
+    pub post_processors: Vec<PostProcessor>,
+}
+
+impl ResolvedOutput {
+    // Caminho passado ao -o do yt-dlp; o modelo pode ter subpastas
+    pub fn template_path(&self) -> PathBuf {
+        self.dir.join(&self.filename_template)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_overrides_rule_and_settings() {
+        let settings = Settings {
+            download_path: "/videos".to_string(),
+            filename_template: "%(title)s.%(ext)s".to_string(),
+            post_processors: vec![PostProcessor::EmbedMetadata],
+            ..Settings::default()
+        };
+        let rule = OutputOptions {
+            output_dir: Some("/videos/youtube".to_string()),
+            filename_template: Some("%(uploader)s/%(title)s.%(ext)s".to_string()),
+            ..OutputOptions::default()
+        };
+        let request = OutputOptions {
+            output_dir: Some("  ".to_string()),
+            filename_template: Some("%(id)s.%(ext)s".to_string()),
+            subtitles: Some(SubtitleOptions {
+                enabled: true,
+                ..SubtitleOptions::default()
+            }),
+            ..OutputOptions::default()
+        };
+
+        let resolved = request.or(&rule).resolve(&settings);
+        assert_eq!(resolved.dir, PathBuf::from("/videos/youtube"));
+        assert_eq!(resolved.template_path(), PathBuf::from("/videos/youtube").join("%(id)s.%(ext)s"));
+        assert!(resolved.subtitles.enabled);
+        assert_eq!(resolved.post_processors, vec![PostProcessor::EmbedMetadata]);
+
+        let resolved = OutputOptions::default().resolve(&settings);
+        assert_eq!(resolved.dir, PathBuf::from("/videos"));
+        assert_eq!(resolved.filename_template, "%(title)s.%(ext)s");
+    }
+
+    #[test]
+    fn builds_subtitle_and_post_processor_args() {
+        let subtitles = SubtitleOptions {
+            enabled: true,
+            auto_generated: true,
+            languages: vec!["pt.*".to_string(), " ".to_string(), "en".to_string()],
+            embed: true,
+            format: Some("srt".to_string()),
+        };
+        assert_eq!(
+            subtitles.args(false),
+            ["--write-subs", "--write-auto-subs", "--sub-langs", "pt.*,en", "--convert-subs", "srt", "--embed-subs"]
+        );
+        assert_eq!(subtitles.args(true)[..3], ["--write-sub", "--write-auto-sub", "--sub-lang"]);
+        assert!(SubtitleOptions { embed: true, ..SubtitleOptions::default() }.args(false).is_empty());
+
+        let post_processors = [PostProcessor::EmbedThumbnail, PostProcessor::SponsorblockRemove];
+        assert_eq!(
+            post_processor_args(&post_processors, false),
+            ["--embed-thumbnail", "--sponsorblock-remove", "default"]
+        );
+        assert_eq!(post_processor_args(&post_processors, true), ["--embed-thumbnail"]);
+    }
+}
@@ -0,0 +1,192 @@
+// Expansão de playlists e canais em vídeos individuais
+
+use serde::{Deserialize, Serialize};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+use url::Url;
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlaylistOptions {
+    // Expandir playlists/canais em itens separados na fila
+    pub expand: bool,
+    // Intervalo de itens no formato do --playlist-items (ex.: "1:10,15")
+    pub items: Option<String>,
+    // Apenas os N primeiros itens (em canais, os mais recentes)
+    pub newest: Option<usize>,
+    // Ignorar vídeos que já constam no histórico
+    pub skip_downloaded: bool,
+}
+
+impl Default for PlaylistOptions {
+    fn default() -> Self {
+        Self {
+            expand: true,
+            items: None,
+            newest: None,
+            skip_downloaded: true,
+        }
+    }
+}
+
+// Canais grandes demoram a listar; sem resposta nesse prazo a expansão desiste
+const EXPAND_TIMEOUT: Duration = Duration::from_secs(180);
+
+#[derive(Debug, Clone)]
+pub struct PlaylistEntry {
+    pub url: String,
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Playlist {
+    pub title: String,
+    pub entries: Vec<PlaylistEntry>,
+}
+
+// Saída do `yt-dlp --flat-playlist -J`
+#[derive(Debug, Deserialize)]
+struct FlatInfo {
+    #[serde(rename = "_type")]
+    kind: Option<String>,
+    id: Option<String>,
+    title: Option<String>,
+    #[serde(default)]
+    entries: Vec<FlatEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FlatEntry {
+    url: Option<String>,
+    webpage_url: Option<String>,
+    title: Option<String>,
+}
+
+// Heurística barata para evitar a consulta ao yt-dlp em URLs de vídeo comuns
+pub fn is_playlist_url(url: &str) -> bool {
+    let url_lower = url.to_lowercase();
+    let (path, query) = match url_lower.split_once('?') {
+        Some((path, query)) => (path, query),
+        None => (url_lower.as_str(), ""),
+    };
+
+    let params: Vec<&str> = query.split('&').collect();
+    let has_param = |name: &str| params.iter().any(|p| p.split('=').next() == Some(name));
+
+    // watch?v=...&list=... é tratado como o vídeo individual
+    if has_param("list") && !has_param("v") {
+        return true;
+    }
+
+    let playlist_paths = [
+        "/playlist",
+        "/channel/",
+        "/c/",
+        "/user/",
+        "/sets/",
+        "/album/",
+        "/showcase/",
+    ];
+
+    playlist_paths.iter().any(|p| path.contains(p)) || is_handle_page(url)
+}
+
+// Perfil por @handle (YouTube, TikTok...) ou uma aba de listagem dele; os vídeos do
+// perfil (ex.: tiktok.com/@x/video/123) continuam sendo itens únicos
+fn is_handle_page(url: &str) -> bool {
+    const TABS: [&str; 5] = ["videos", "streams", "shorts", "playlists", "featured"];
+
+    let Ok(parsed) = Url::parse(url) else {
+        return false;
+    };
+    let Some(rest) = parsed.path().strip_prefix("/@") else {
+        return false;
+    };
+
+    let mut parts = rest.trim_end_matches('/').split('/');
+    let handle = parts.next().unwrap_or_default();
+    let tab = parts.next().map(str::to_lowercase);
+    !handle.is_empty() && parts.next().is_none() && tab.is_none_or(|tab| TABS.contains(&tab.as_str()))
+}
+
+// Lista os itens da playlist sem baixá-los; Ok(None) se a URL for um vídeo único
+pub async fn expand_playlist(ytdlp: &str, url: &str, options: &PlaylistOptions) -> Result<Option<Playlist>, String> {
+    let mut command = Command::new(ytdlp);
+    command.arg("--flat-playlist").arg("-J");
+
+    let newest = options.newest.filter(|n| *n > 0);
+    if let Some(items) = options.items.as_deref().map(str::trim).filter(|i| !i.is_empty()) {
+        command.arg("--playlist-items").arg(items);
+    } else if let Some(newest) = newest {
+        // O yt-dlp para de listar no item N em vez de percorrer o canal inteiro
+        command.arg("--playlist-end").arg(newest.to_string());
+    }
+
+    command
+        .arg("--")
+        .arg(url)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    #[cfg(target_os = "windows")]
+    command.creation_flags(0x08000000);
+
+    let output = tokio::time::timeout(EXPAND_TIMEOUT, command.output())
+        .await
+        .map_err(|_| "o yt-dlp demorou demais para listar a playlist".to_string())?
+        .map_err(|e| e.to_string())?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = stderr.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("erro desconhecido");
+        return Err(message.trim().to_string());
+    }
+
+    let info: FlatInfo = serde_json::from_slice(&output.stdout).map_err(|e| e.to_string())?;
+
+    if info.kind.as_deref() != Some("playlist") {
+        return Ok(None);
+    }
+
+    let mut entries: Vec<PlaylistEntry> = info
+        .entries
+        .into_iter()
+        .filter_map(|entry| {
+            let url = entry.url.or(entry.webpage_url)?;
+            Some(PlaylistEntry { url, title: entry.title })
+        })
+        .collect();
+
+    // Com --playlist-items o --playlist-end não vale; o corte fica para depois da lista
+    if let Some(newest) = newest {
+        entries.truncate(newest);
+    }
+
+    Ok(Some(Playlist {
+        title: info.title.or(info.id).unwrap_or_else(|| url.to_string()),
+        entries,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handle_urls_are_playlists_only_for_the_profile_and_its_tabs() {
+        assert!(is_playlist_url("https://www.youtube.com/@canal"));
+        assert!(is_playlist_url("https://www.youtube.com/@canal/videos"));
+        assert!(is_playlist_url("https://www.youtube.com/@canal/streams/"));
+        assert!(is_playlist_url("https://www.tiktok.com/@perfil?lang=pt-BR"));
+
+        assert!(!is_playlist_url("https://www.tiktok.com/@perfil/video/7301234567890123456"));
+        assert!(!is_playlist_url("https://www.youtube.com/@canal/live"));
+        assert!(!is_playlist_url("https://www.youtube.com/watch?v=abc&list=PL1"));
+        assert!(is_playlist_url("https://www.youtube.com/playlist?list=PL1"));
+    }
+}
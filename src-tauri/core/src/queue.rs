@@ -0,0 +1,1088 @@
+// Fila de downloads: itens pendentes, ativos, pausados e com falha, salva em queue.json
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+use crate::canonical;
+use crate::errors::DownloadFailure;
+use crate::limits::{self, HostLimit, HostStarts};
+use crate::output::OutputOptions;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DownloadProgress {
+    pub percent: Option<f64>,
+    pub downloaded_bytes: Option<u64>,
+    pub total_bytes: Option<u64>,
+    pub speed: Option<f64>,
+    pub eta: Option<u64>,
+    pub fragment_index: Option<u64>,
+    pub fragment_count: Option<u64>,
+}
+
+// Ordem das variantes define a ordenação: High > Normal > Low
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+// Item da fila: URL e opções escolhidas ao adicioná-la
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadRequest {
+    pub url: String,
+    pub profile: Option<String>,
+    pub title: Option<String>,
+    // Playlist/canal de origem (id do DownloadGroup)
+    pub group: Option<String>,
+    // Valores próprios do pedido; o que faltar vem das configurações ao baixar
+    #[serde(flatten)]
+    pub output: OutputOptions,
+    // Tentativas já feitas e quando a próxima pode começar
+    #[serde(default)]
+    pub attempts: u32,
+    pub retry_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub priority: Priority,
+    // Começa assim que possível, mesmo acima de max_concurrent
+    #[serde(default)]
+    pub pinned: bool,
+    // Reiniciado pelo agendador (nova divisão de banda, fim do horário); sem nova notificação
+    #[serde(skip)]
+    pub restarted: bool,
+}
+
+impl DownloadRequest {
+    pub fn new(url: String, profile: Option<String>) -> Self {
+        Self {
+            url,
+            profile,
+            title: None,
+            group: None,
+            output: OutputOptions::default(),
+            attempts: 0,
+            retry_at: None,
+            priority: Priority::Normal,
+            pinned: false,
+            restarted: false,
+        }
+    }
+}
+
+// Download que falhou de vez; fica à espera de uma nova tentativa manual
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailedDownload {
+    pub request: DownloadRequest,
+    pub failure: DownloadFailure,
+    pub failed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadItem {
+    pub url: String,
+    pub status: String,
+    pub profile: Option<String>,
+    pub title: Option<String>,
+    pub group: Option<String>,
+    pub progress: Option<DownloadProgress>,
+    pub attempts: u32,
+    pub retry_at: Option<DateTime<Utc>>,
+    pub priority: Priority,
+    pub pinned: bool,
+    pub error: Option<String>,
+}
+
+impl DownloadItem {
+    pub fn new(request: &DownloadRequest, status: &str, progress: Option<DownloadProgress>) -> Self {
+        Self {
+            url: request.url.clone(),
+            status: status.to_string(),
+            profile: request.profile.clone(),
+            title: request.title.clone(),
+            group: request.group.clone(),
+            progress,
+            attempts: request.attempts,
+            retry_at: request.retry_at,
+            priority: request.priority,
+            pinned: request.pinned,
+            error: None,
+        }
+    }
+}
+
+// Playlist ou canal expandido em vários itens da fila
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadGroup {
+    pub id: String,
+    pub title: String,
+    pub total: usize,
+    pub finished: usize,
+    pub failed: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadGroupStatus {
+    #[serde(flatten)]
+    pub group: DownloadGroup,
+    pub percent: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadStatus {
+    pub active: usize,
+    pub queued: usize,
+    pub paused: usize,
+    pub failed: usize,
+    pub downloads: Vec<DownloadItem>,
+    pub groups: Vec<DownloadGroupStatus>,
+}
+
+// Tempo mínimo de execução antes de um download poder ser reiniciado pela divisão de banda
+const REBALANCE_MIN_RUNTIME: Duration = Duration::from_secs(60);
+// Intervalo mínimo entre duas rodadas de reinícios
+const REBALANCE_INTERVAL: Duration = Duration::from_secs(300);
+
+// Reinicia só quando o limite entra ou sai de vigor ou a parte muda mais que o dobro;
+// mudanças menores (2 → 3 ativos) valem apenas para os downloads que começarem depois
+fn needs_restart(rate: Option<u64>, share: Option<u64>, running_for: Duration) -> bool {
+    if running_for < REBALANCE_MIN_RUNTIME {
+        return false;
+    }
+    match (rate, share) {
+        (Some(rate), Some(share)) => rate > share.saturating_mul(2) || rate.saturating_mul(2) < share,
+        (None, None) => false,
+        _ => true,
+    }
+}
+
+// Entra depois de todos os itens de prioridade igual ou maior
+fn insert_by_priority(queue: &mut VecDeque<DownloadRequest>, request: DownloadRequest) {
+    let pos = queue
+        .iter()
+        .position(|r| r.priority < request.priority)
+        .unwrap_or(queue.len());
+    queue.insert(pos, request);
+}
+
+// Estado da fila salvo em disco para sobreviver a reinícios
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct QueueSnapshot {
+    queued: Vec<DownloadRequest>,
+    active: Vec<DownloadRequest>,
+    paused: Vec<DownloadRequest>,
+    failed: Vec<FailedDownload>,
+    groups: Vec<DownloadGroup>,
+}
+
+// O que fazer com os downloads ativos ao sair
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuitMode {
+    Finish,
+    Pause,
+    Drop,
+    // Volta os ativos ao início da fila; na próxima execução continuam dos arquivos .part
+    Requeue,
+}
+
+// Destino de um item ao sair da lista de ativos
+pub enum ActiveOutcome {
+    Done,
+    Paused,
+    Retry(DateTime<Utc>),
+    Failed(DownloadFailure),
+    Requeue,
+}
+
+// Motivo de interrupção de um download ativo
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StopReason {
+    Cancel,
+    Pause,
+    // Interrompido pelo agendador; volta ao início da fila e continua do arquivo .part
+    Requeue,
+}
+
+// Fila de downloads
+#[derive(Default)]
+pub struct DownloadQueue {
+    queue: Arc<Mutex<VecDeque<DownloadRequest>>>,
+    active: Arc<Mutex<Vec<DownloadRequest>>>,
+    paused: Arc<Mutex<Vec<DownloadRequest>>>,
+    failed: Arc<Mutex<Vec<FailedDownload>>>,
+    groups: Arc<Mutex<HashMap<String, DownloadGroup>>>,
+    progress: Arc<Mutex<HashMap<String, DownloadProgress>>>,
+    // Sinal para encerrar o processo do yt-dlp de cada download ativo
+    kill_switches: Arc<Mutex<HashMap<String, Arc<Notify>>>>,
+    stop_requests: Arc<Mutex<HashMap<String, StopReason>>>,
+    logs: Arc<Mutex<HashMap<String, Vec<String>>>>,
+    host_starts: Arc<Mutex<HostStarts>>,
+    // Limite de banda (KiB/s) com que cada download ativo foi iniciado, e quando
+    rates: Mutex<HashMap<String, (Option<u64>, Instant)>>,
+    last_rebalance: Mutex<Option<Instant>>,
+    // Ao sair: não inicia novos downloads
+    draining: AtomicBool,
+    state_path: Option<PathBuf>,
+    // Acorda o agendador: itens novos, downloads finalizados, configurações alteradas
+    wake: Notify,
+}
+
+impl DownloadQueue {
+    // Fila salva em queue.json; downloads que estavam ativos voltam ao início da fila
+    // e continuam a partir dos arquivos .part
+    pub fn with_persistence(config_dir: &Path) -> Self {
+        let state_path = config_dir.join("queue.json");
+        let snapshot: QueueSnapshot = fs::read_to_string(&state_path)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+
+        let queue = Self {
+            state_path: Some(state_path),
+            ..Self::default()
+        };
+
+        {
+            let mut queued = queue.queue.lock().unwrap();
+            queued.extend(snapshot.active);
+            queued.extend(snapshot.queued);
+        }
+        *queue.paused.lock().unwrap() = snapshot.paused;
+        *queue.failed.lock().unwrap() = snapshot.failed;
+        *queue.groups.lock().unwrap() = snapshot
+            .groups
+            .into_iter()
+            .map(|group| (group.id.clone(), group))
+            .collect();
+
+        queue
+    }
+
+    fn persist(&self) {
+        let Some(path) = &self.state_path else {
+            return;
+        };
+
+        let snapshot = QueueSnapshot {
+            queued: self.queue.lock().unwrap().iter().cloned().collect(),
+            active: self.active.lock().unwrap().clone(),
+            paused: self.paused.lock().unwrap().clone(),
+            failed: self.failed.lock().unwrap().clone(),
+            groups: self.groups.lock().unwrap().values().cloned().collect(),
+        };
+
+        // Grava em arquivo temporário e renomeia para não corromper a fila se o app cair no meio
+        if let Ok(json) = serde_json::to_string_pretty(&snapshot) {
+            let tmp_path = path.with_extension("json.tmp");
+            if fs::write(&tmp_path, json).is_ok() {
+                fs::rename(&tmp_path, path).ok();
+            }
+        }
+    }
+
+    fn push(&self, request: DownloadRequest) -> bool {
+        let mut queue = self.queue.lock().unwrap();
+        let active = self.active.lock().unwrap();
+        let paused = self.paused.lock().unwrap();
+        
+        // O mesmo vídeo com outra URL (youtu.be, shorts, parâmetros de rastreamento) conta como repetido
+        let key = canonical::video_key(&request.url);
+        let same_video = |r: &DownloadRequest| r.url == request.url || canonical::video_key(&r.url) == key;
+        if queue.iter().chain(active.iter()).chain(paused.iter()).any(same_video) {
+            return false;
+        }
+        
+        // Adicionar de novo um vídeo que falhou equivale a tentar de novo; a falha deixa de
+        // contar no grupo dela, e o item volta a contar só se vier de novo numa playlist
+        let mut failed = self.failed.lock().unwrap();
+        let mut groups = self.groups.lock().unwrap();
+        failed.retain(|f| {
+            if !same_video(&f.request) {
+                return true;
+            }
+            if let Some(group) = f.request.group.as_deref().and_then(|id| groups.get_mut(id)) {
+                group.total = group.total.saturating_sub(1);
+                group.finished = group.finished.saturating_sub(1);
+                group.failed = group.failed.saturating_sub(1);
+            }
+            false
+        });
+        drop((failed, groups));
+        insert_by_priority(&mut queue, request);
+        true
+    }
+
+    pub fn add(&self, request: DownloadRequest) -> bool {
+        let added = self.push(request);
+        if added {
+            self.persist();
+            self.wake();
+        }
+        added
+    }
+
+    pub fn wake(&self) {
+        self.wake.notify_one();
+    }
+
+    // Espera até alguém acordar o agendador, um item bloqueado por prazo ser liberado
+    // ou a agenda de banda mudar (`schedule_change`)
+    pub async fn wait_for_change(&self, limits: &[HostLimit], schedule_change: Option<Duration>) {
+        match self.next_ready_in(limits).into_iter().chain(schedule_change).min() {
+            Some(delay) => {
+                tokio::select! {
+                    _ = self.wake.notified() => {}
+                    _ = tokio::time::sleep(delay) => {}
+                }
+            }
+            None => self.wake.notified().await,
+        }
+    }
+
+    // Prazo mais próximo entre novas tentativas e intervalos por site. Itens bloqueados só
+    // pelo número de downloads simultâneos esperam uma vaga, que acorda o agendador
+    pub fn next_ready_in(&self, limits: &[HostLimit]) -> Option<Duration> {
+        let now = Utc::now();
+        let queue = self.queue.lock().unwrap();
+        let host_starts = self.host_starts.lock().unwrap();
+
+        queue
+            .iter()
+            .filter_map(|r| {
+                let retry = r.retry_at.and_then(|at| (at - now).to_std().ok());
+                let host = limits::find_limit(limits, &r.url)
+                    .and_then(|limit| host_starts.remaining_delay(limit));
+                retry.into_iter().chain(host).max()
+            })
+            .filter(|delay| !delay.is_zero())
+            .min()
+    }
+
+    // Adiciona os itens de uma playlist ao grupo dela, novo ou ainda em andamento; o total
+    // considera só os realmente enfileirados. Cada item conta antes de entrar na fila, para
+    // não terminar antes de o grupo saber dele
+    pub fn add_group(&self, group: DownloadGroup, requests: Vec<DownloadRequest>) -> usize {
+        let empty = DownloadGroup {
+            total: 0,
+            finished: 0,
+            failed: 0,
+            ..group
+        };
+
+        let mut added = 0;
+        for request in requests {
+            self.groups
+                .lock()
+                .unwrap()
+                .entry(empty.id.clone())
+                .or_insert_with(|| empty.clone())
+                .total += 1;
+            if self.push(request) {
+                added += 1;
+                continue;
+            }
+
+            let mut groups = self.groups.lock().unwrap();
+            if let Some(group) = groups.get_mut(&empty.id) {
+                group.total = group.total.saturating_sub(1);
+                if group.finished >= group.total {
+                    groups.remove(&empty.id);
+                }
+            }
+        }
+        self.persist();
+        self.wake();
+        added
+    }
+
+    pub fn finish_group_item(&self, group_id: &str, success: bool) {
+        let mut groups = self.groups.lock().unwrap();
+        if let Some(group) = groups.get_mut(group_id) {
+            group.finished += 1;
+            if !success {
+                group.failed += 1;
+            }
+            if group.finished >= group.total {
+                groups.remove(group_id);
+            }
+        }
+        drop(groups);
+        self.persist();
+    }
+
+    // Item cancelado deixa de contar no grupo
+    pub fn forget_group_item(&self, group_id: &str) {
+        let mut groups = self.groups.lock().unwrap();
+        if let Some(group) = groups.get_mut(group_id) {
+            group.total = group.total.saturating_sub(1);
+            if group.finished >= group.total {
+                groups.remove(group_id);
+            }
+        }
+        drop(groups);
+        self.persist();
+    }
+
+    // Primeiro item da fila que não esteja aguardando nova tentativa nem bloqueado pelo limite
+    // do seu site; um site no limite não impede que os demais comecem
+    pub fn get_next(&self, limits: &[HostLimit]) -> Option<DownloadRequest> {
+        let now = Utc::now();
+        let mut queue = self.queue.lock().unwrap();
+        let active = self.active.lock().unwrap();
+        let mut host_starts = self.host_starts.lock().unwrap();
+
+        let pos = queue.iter().position(|r| {
+            if matches!(r.retry_at, Some(at) if at > now) {
+                return false;
+            }
+
+            let Some(limit) = limits::find_limit(limits, &r.url) else {
+                return true;
+            };
+
+            let running = active
+                .iter()
+                .filter(|a| limits::find_limit(limits, &a.url).is_some_and(|l| l.host == limit.host))
+                .count();
+
+            (limit.max_concurrent == 0 || running < limit.max_concurrent)
+                && host_starts.remaining_delay(limit).is_none()
+        })?;
+
+        let request = queue.remove(pos)?;
+        if let Some(limit) = limits::find_limit(limits, &request.url) {
+            host_starts.record(limit);
+        }
+        Some(request)
+    }
+
+    // Itens fixados não ocupam vaga
+    pub fn can_start(&self, max_concurrent: usize) -> bool {
+        !self.draining.load(Ordering::SeqCst)
+            && self.active.lock().unwrap().iter().filter(|r| !r.pinned).count() < max_concurrent
+    }
+
+    // Item fixado ignora também os limites por site, mas conta como início para o intervalo
+    pub fn take_pinned(&self, limits: &[HostLimit]) -> Option<DownloadRequest> {
+        if self.draining.load(Ordering::SeqCst) {
+            return None;
+        }
+        let mut queue = self.queue.lock().unwrap();
+        let pos = queue.iter().position(|r| r.pinned)?;
+        let request = queue.remove(pos)?;
+
+        if let Some(limit) = limits::find_limit(limits, &request.url) {
+            self.host_starts.lock().unwrap().record(limit);
+        }
+        Some(request)
+    }
+
+    // Aplica uma mudança de posição a um item da fila; false se a URL não estiver na fila
+    fn reorder(&self, url: &str, change: impl FnOnce(&mut VecDeque<DownloadRequest>, usize)) -> bool {
+        {
+            let mut queue = self.queue.lock().unwrap();
+            let Some(pos) = queue.iter().position(|r| r.url == url) else {
+                return false;
+            };
+            change(&mut queue, pos);
+        }
+        self.persist();
+        self.wake();
+        true
+    }
+
+    pub fn move_up(&self, url: &str) -> bool {
+        self.reorder(url, |queue, pos| {
+            if pos > 0 {
+                queue.swap(pos, pos - 1);
+            }
+        })
+    }
+
+    pub fn move_down(&self, url: &str) -> bool {
+        self.reorder(url, |queue, pos| {
+            if pos + 1 < queue.len() {
+                queue.swap(pos, pos + 1);
+            }
+        })
+    }
+
+    pub fn download_next(&self, url: &str) -> bool {
+        self.reorder(url, |queue, pos| {
+            if let Some(request) = queue.remove(pos) {
+                queue.push_front(request);
+            }
+        })
+    }
+
+    pub fn set_priority(&self, url: &str, priority: Priority) -> bool {
+        if let Some(request) = self.paused.lock().unwrap().iter_mut().find(|r| r.url == url) {
+            request.priority = priority;
+        }
+
+        self.reorder(url, |queue, pos| {
+            if let Some(mut request) = queue.remove(pos) {
+                request.priority = priority;
+                insert_by_priority(queue, request);
+            }
+        })
+    }
+
+    // Só um item fixado por vez; ele passa na frente e ignora o intervalo de nova tentativa
+    pub fn pin(&self, url: &str) -> bool {
+        for request in self.paused.lock().unwrap().iter_mut() {
+            request.pinned = false;
+        }
+
+        self.reorder(url, |queue, pos| {
+            for request in queue.iter_mut() {
+                request.pinned = false;
+            }
+            if let Some(mut request) = queue.remove(pos) {
+                request.pinned = true;
+                request.retry_at = None;
+                queue.push_front(request);
+            }
+        })
+    }
+
+    pub fn mark_active(&self, request: DownloadRequest) {
+        self.active.lock().unwrap().push(request);
+        self.persist();
+    }
+
+    // Tira o item dos ativos e o leva direto ao destino (pausados, fila, falhas)
+    // para que o arquivo da fila nunca fique sem ele
+    pub fn remove_active(&self, url: &str, outcome: ActiveOutcome) {
+        {
+            let mut queue = self.queue.lock().unwrap();
+            let mut active = self.active.lock().unwrap();
+            let removed = active.iter().position(|r| r.url == url).map(|pos| active.remove(pos));
+            // Pedido de parada que chegou depois de o worker ler o dele não vale para a próxima execução
+            self.stop_requests.lock().unwrap().remove(url);
+
+            if let Some(mut request) = removed {
+                // A fixação vale só para a execução que ela iniciou, exceto em reinícios do agendador
+                request.restarted = matches!(outcome, ActiveOutcome::Requeue);
+                if !request.restarted {
+                    request.pinned = false;
+                }
+                match outcome {
+                    ActiveOutcome::Done => {}
+                    ActiveOutcome::Requeue => queue.push_front(request),
+                    ActiveOutcome::Paused => self.paused.lock().unwrap().push(request),
+                    ActiveOutcome::Retry(at) => {
+                        request.attempts += 1;
+                        request.retry_at = Some(at);
+                        insert_by_priority(&mut queue, request);
+                    }
+                    ActiveOutcome::Failed(failure) => {
+                        request.attempts += 1;
+                        self.failed.lock().unwrap().push(FailedDownload {
+                            request,
+                            failure,
+                            failed_at: Utc::now(),
+                        });
+                    }
+                }
+            }
+        }
+        self.progress.lock().unwrap().remove(url);
+        self.logs.lock().unwrap().remove(url);
+        self.rates.lock().unwrap().remove(url);
+        self.persist();
+        self.wake();
+    }
+
+    pub fn set_rate(&self, url: &str, rate: Option<u64>) {
+        self.rates.lock().unwrap().insert(url.to_string(), (rate, Instant::now()));
+    }
+
+    // Reinicia os downloads cuja parte da banda ficou muito diferente da nova divisão;
+    // o yt-dlp não muda o --limit-rate de um processo em andamento. No máximo uma vez
+    // a cada REBALANCE_INTERVAL, para entradas e saídas da fila não derrubarem todos os ativos
+    pub fn rebalance(&self, share: Option<u64>) {
+        let mut last_rebalance = self.last_rebalance.lock().unwrap();
+        if last_rebalance.is_some_and(|at| at.elapsed() < REBALANCE_INTERVAL) {
+            return;
+        }
+
+        let urls: Vec<String> = {
+            let rates = self.rates.lock().unwrap();
+            let stop_requests = self.stop_requests.lock().unwrap();
+            rates
+                .iter()
+                .filter(|(url, (rate, since))| {
+                    needs_restart(*rate, share, since.elapsed()) && !stop_requests.contains_key(*url)
+                })
+                .map(|(url, _)| url.clone())
+                .collect()
+        };
+
+        if urls.is_empty() {
+            return;
+        }
+        *last_rebalance = Some(Instant::now());
+        drop(last_rebalance);
+
+        for url in urls {
+            self.stop_active(&url, StopReason::Requeue);
+        }
+    }
+
+    // Fim do horário permitido: downloads não fixados voltam para a fila
+    pub fn requeue_active(&self) {
+        let urls: Vec<String> = self
+            .active
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| !r.pinned)
+            .map(|r| r.url.clone())
+            .collect();
+
+        let stop_requests: HashSet<String> = self.stop_requests.lock().unwrap().keys().cloned().collect();
+        for url in urls.into_iter().filter(|url| !stop_requests.contains(url)) {
+            self.stop_active(&url, StopReason::Requeue);
+        }
+    }
+
+    pub fn append_log(&self, url: &str, line: &str) {
+        self.logs
+            .lock()
+            .unwrap()
+            .entry(url.to_string())
+            .or_default()
+            .push(line.to_string());
+    }
+
+    // Log parcial de um download em andamento
+    pub fn log(&self, url: &str) -> Option<String> {
+        self.logs.lock().unwrap().get(url).map(|lines| lines.join("\n"))
+    }
+
+    pub fn active_count(&self) -> usize {
+        self.active.lock().unwrap().len()
+    }
+
+    // Para de iniciar downloads e interrompe os ativos conforme o modo escolhido
+    pub fn begin_shutdown(&self, mode: QuitMode) {
+        self.draining.store(true, Ordering::SeqCst);
+
+        let reason = match mode {
+            QuitMode::Finish => return,
+            QuitMode::Pause => StopReason::Pause,
+            QuitMode::Drop => StopReason::Cancel,
+            QuitMode::Requeue => StopReason::Requeue,
+        };
+
+        let urls: Vec<String> = self.active.lock().unwrap().iter().map(|r| r.url.clone()).collect();
+        for url in urls {
+            self.stop_active(&url, reason);
+        }
+    }
+
+    pub fn update_progress(&self, url: &str, progress: DownloadProgress) {
+        self.progress.lock().unwrap().insert(url.to_string(), progress);
+    }
+
+    // Sinal para interromper o yt-dlp; o Notify guarda o aviso, então um pedido
+    // de parada anterior ao registro não se perde
+    pub fn register_kill_switch(&self, url: &str) -> Arc<Notify> {
+        let kill = Arc::new(Notify::new());
+        if self.stop_requests.lock().unwrap().contains_key(url) {
+            kill.notify_one();
+        }
+        self.kill_switches.lock().unwrap().insert(url.to_string(), kill.clone());
+        kill
+    }
+
+    pub fn remove_kill_switch(&self, url: &str) {
+        self.kill_switches.lock().unwrap().remove(url);
+    }
+
+    pub fn stop_request(&self, url: &str) -> Option<StopReason> {
+        self.stop_requests.lock().unwrap().get(url).copied()
+    }
+
+    pub fn take_stop_request(&self, url: &str) -> Option<StopReason> {
+        self.stop_requests.lock().unwrap().remove(url)
+    }
+
+    // Interrompe um download ativo; o processo é finalizado e o worker trata o motivo.
+    // O pedido é gravado sob a trava dos ativos: remove_active descarta os que chegam tarde
+    pub fn stop_active(&self, url: &str, reason: StopReason) -> bool {
+        let active = self.active.lock().unwrap();
+        if !active.iter().any(|r| r.url == url) {
+            return false;
+        }
+
+        self.stop_requests.lock().unwrap().insert(url.to_string(), reason);
+        drop(active);
+        if let Some(kill) = self.kill_switches.lock().unwrap().get(url) {
+            kill.notify_one();
+        }
+        true
+    }
+
+    pub fn cancel(&self, url: &str) -> bool {
+        let removed = {
+            let mut queue = self.queue.lock().unwrap();
+            queue.iter().position(|r| r.url == url).and_then(|pos| queue.remove(pos))
+        }
+        .or_else(|| {
+            let mut paused = self.paused.lock().unwrap();
+            paused.iter().position(|r| r.url == url).map(|pos| paused.remove(pos))
+        });
+
+        match removed {
+            Some(request) => {
+                if let Some(group) = &request.group {
+                    self.forget_group_item(group);
+                }
+                self.persist();
+                true
+            }
+            None => self.stop_active(url, StopReason::Cancel),
+        }
+    }
+
+    pub fn pause(&self, url: &str) -> bool {
+        let queued = {
+            let mut queue = self.queue.lock().unwrap();
+            queue.iter().position(|r| r.url == url).and_then(|pos| queue.remove(pos))
+        };
+
+        if let Some(request) = queued {
+            self.mark_paused(request);
+            return true;
+        }
+
+        self.stop_active(url, StopReason::Pause)
+    }
+
+    // Devolve um item pausado ao início da fila; o yt-dlp continua a partir do arquivo .part
+    pub fn resume(&self, url: &str) -> bool {
+        let paused = {
+            let mut paused = self.paused.lock().unwrap();
+            paused.iter().position(|r| r.url == url).map(|pos| paused.remove(pos))
+        };
+
+        match paused {
+            Some(request) => {
+                self.queue.lock().unwrap().push_front(request);
+                self.persist();
+                self.wake();
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Nova tentativa manual de um download que falhou: volta ao início da fila
+    pub fn retry_failed(&self, url: &str) -> bool {
+        let failed = {
+            let mut failed = self.failed.lock().unwrap();
+            failed.iter().position(|f| f.request.url == url).map(|pos| failed.remove(pos))
+        };
+
+        let Some(FailedDownload { mut request, .. }) = failed else {
+            return false;
+        };
+
+        // A falha já contou no grupo; volta a ser um item pendente
+        if let Some(group) = request.group.as_deref() {
+            if let Some(group) = self.groups.lock().unwrap().get_mut(group) {
+                group.finished = group.finished.saturating_sub(1);
+                group.failed = group.failed.saturating_sub(1);
+            }
+        }
+
+        request.attempts = 0;
+        request.retry_at = None;
+        self.queue.lock().unwrap().push_front(request);
+        self.persist();
+        self.wake();
+        true
+    }
+
+    pub fn dismiss_failed(&self, url: &str) -> bool {
+        let removed = {
+            let mut failed = self.failed.lock().unwrap();
+            let before = failed.len();
+            failed.retain(|f| f.request.url != url);
+            failed.len() != before
+        };
+
+        if removed {
+            self.persist();
+        }
+        removed
+    }
+
+    pub fn mark_paused(&self, request: DownloadRequest) {
+        self.paused.lock().unwrap().push(request);
+        self.persist();
+    }
+
+    pub fn get_status(&self) -> DownloadStatus {
+        let queue = self.queue.lock().unwrap();
+        let active = self.active.lock().unwrap();
+        let paused = self.paused.lock().unwrap();
+        let progress = self.progress.lock().unwrap();
+        let groups = self.groups.lock().unwrap();
+        
+        let mut downloads = Vec::new();
+        
+        for request in active.iter() {
+            downloads.push(DownloadItem::new(request, "active", progress.get(&request.url).cloned()));
+        }
+        
+        for request in queue.iter() {
+            downloads.push(DownloadItem::new(request, "queued", None));
+        }
+        
+        for request in paused.iter() {
+            downloads.push(DownloadItem::new(request, "paused", None));
+        }
+        
+        let failed = self.failed.lock().unwrap();
+        for entry in failed.iter() {
+            downloads.push(DownloadItem {
+                error: Some(entry.failure.reason()),
+                ..DownloadItem::new(&entry.request, "failed", None)
+            });
+        }
+        
+        // Progresso agregado: itens finalizados mais a fração dos ativos
+        let groups = groups
+            .values()
+            .map(|group| {
+                let active_fraction: f64 = active
+                    .iter()
+                    .filter(|r| r.group.as_deref() == Some(group.id.as_str()))
+                    .filter_map(|r| progress.get(&r.url).and_then(|p| p.percent))
+                    .map(|percent| percent / 100.0)
+                    .sum();
+                let percent = if group.total > 0 {
+                    ((group.finished as f64 + active_fraction) / group.total as f64 * 100.0).min(100.0)
+                } else {
+                    0.0
+                };
+                DownloadGroupStatus {
+                    group: group.clone(),
+                    percent,
+                }
+            })
+            .collect();
+        
+        DownloadStatus {
+            active: active.len(),
+            queued: queue.len(),
+            paused: paused.len(),
+            failed: failed.len(),
+            downloads,
+            groups,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(url: &str, priority: Priority) -> DownloadRequest {
+        DownloadRequest {
+            priority,
+            ..DownloadRequest::new(url.to_string(), None)
+        }
+    }
+
+    fn next_urls(queue: &DownloadQueue, limits: &[HostLimit]) -> Vec<String> {
+        std::iter::from_fn(|| queue.get_next(limits)).map(|r| r.url).collect()
+    }
+
+    #[test]
+    fn get_next_follows_priority_then_arrival() {
+        let queue = DownloadQueue::default();
+        queue.add(request("https://a.test/low", Priority::Low));
+        queue.add(request("https://a.test/normal1", Priority::Normal));
+        queue.add(request("https://a.test/high", Priority::High));
+        queue.add(request("https://a.test/normal2", Priority::Normal));
+        queue.add(DownloadRequest {
+            retry_at: Some(Utc::now() + chrono::Duration::hours(1)),
+            ..request("https://a.test/waiting", Priority::High)
+        });
+
+        assert!(queue.set_priority("https://a.test/normal2", Priority::High));
+        assert_eq!(
+            next_urls(&queue, &[]),
+            ["https://a.test/high", "https://a.test/normal2", "https://a.test/normal1", "https://a.test/low"]
+        );
+        // O item à espera da nova tentativa continua na fila
+        assert_eq!(queue.get_status().queued, 1);
+    }
+
+    #[test]
+    fn re_adding_a_playlist_merges_into_the_running_group() {
+        let queue = DownloadQueue::default();
+        let group = DownloadGroup {
+            id: "https://a.test/playlist".to_string(),
+            title: "Lista".to_string(),
+            total: 0,
+            finished: 0,
+            failed: 0,
+        };
+        let item = |url: &str| DownloadRequest {
+            group: Some(group.id.clone()),
+            ..request(url, Priority::Normal)
+        };
+        let counts = |queue: &DownloadQueue| {
+            queue.get_status().groups.iter().map(|g| (g.group.total, g.group.finished, g.group.failed)).collect::<Vec<_>>()
+        };
+
+        assert_eq!(queue.add_group(group.clone(), vec![item("https://a.test/1"), item("https://a.test/2")]), 2);
+        queue.finish_group_item(&group.id, false);
+        assert_eq!(counts(&queue), [(2, 1, 1)]);
+
+        // Nada novo: o grupo em andamento continua como estava
+        assert_eq!(queue.add_group(group.clone(), vec![item("https://a.test/2")]), 0);
+        assert_eq!(counts(&queue), [(2, 1, 1)]);
+
+        assert_eq!(queue.add_group(group.clone(), vec![item("https://a.test/2"), item("https://a.test/3")]), 1);
+        assert_eq!(counts(&queue), [(3, 1, 1)]);
+
+        // Lista sem itens novos não cria grupo
+        let other = DownloadGroup {
+            id: "https://a.test/other".to_string(),
+            ..group.clone()
+        };
+        assert_eq!(queue.add_group(other, vec![item("https://a.test/3")]), 0);
+        assert_eq!(counts(&queue).len(), 1);
+    }
+
+    #[test]
+    fn only_one_item_is_pinned_and_it_skips_the_line() {
+        let queue = DownloadQueue::default();
+        assert!(queue.take_pinned(&[]).is_none());
+
+        queue.add(request("https://a.test/1", Priority::High));
+        queue.add(DownloadRequest {
+            retry_at: Some(Utc::now() + chrono::Duration::hours(1)),
+            ..request("https://a.test/2", Priority::Low)
+        });
+        queue.add(request("https://a.test/3", Priority::Low));
+
+        assert!(queue.pin("https://a.test/3"));
+        assert!(queue.pin("https://a.test/2"));
+        assert!(!queue.pin("https://a.test/missing"));
+
+        let pinned = queue.take_pinned(&[]).unwrap();
+        assert_eq!(pinned.url, "https://a.test/2");
+        assert_eq!(pinned.retry_at, None);
+        assert!(queue.take_pinned(&[]).is_none());
+
+        // Fixado ativo não ocupa vaga do max_concurrent
+        queue.mark_active(pinned);
+        assert!(queue.can_start(1));
+        // O item que perdeu a fixação continua à frente
+        let next = queue.get_next(&[]).unwrap();
+        assert_eq!(next.url, "https://a.test/3");
+        queue.mark_active(next);
+        assert!(!queue.can_start(1));
+        assert_eq!(next_urls(&queue, &[]), ["https://a.test/1"]);
+    }
+
+    #[test]
+    fn get_next_respects_per_host_limits() {
+        let limits = [HostLimit {
+            host: "tiktok.com".to_string(),
+            max_concurrent: 1,
+            min_delay_secs: 0,
+        }];
+        let queue = DownloadQueue::default();
+        queue.add(request("https://www.tiktok.com/@a/video/1", Priority::High));
+        queue.add(request("https://m.tiktok.com/@a/video/2", Priority::High));
+        queue.add(request("https://a.test/1", Priority::Normal));
+
+        let first = queue.get_next(&limits).unwrap();
+        assert_eq!(first.url, "https://www.tiktok.com/@a/video/1");
+        queue.mark_active(first);
+
+        // O segundo do mesmo site espera a vaga mesmo com prioridade maior
+        assert_eq!(next_urls(&queue, &limits), ["https://a.test/1"]);
+        queue.remove_active("https://www.tiktok.com/@a/video/1", ActiveOutcome::Done);
+        assert_eq!(next_urls(&queue, &limits), ["https://m.tiktok.com/@a/video/2"]);
+
+        // Com intervalo mínimo, o próximo início do site espera o prazo
+        let polite = [HostLimit {
+            min_delay_secs: 60,
+            ..limits[0].clone()
+        }];
+        let queue = DownloadQueue::default();
+        queue.add(request("https://www.tiktok.com/@a/video/3", Priority::Normal));
+        queue.add(request("https://www.tiktok.com/@a/video/4", Priority::Normal));
+        assert_eq!(queue.get_next(&polite).unwrap().url, "https://www.tiktok.com/@a/video/3");
+        assert!(queue.get_next(&polite).is_none());
+    }
+
+    #[test]
+    fn rebalance_restarts_only_long_running_downloads_far_from_the_share() {
+        let running = Duration::from_secs(120);
+        // 2 → 3 ativos: a nova parte vale só para os próximos
+        assert!(!needs_restart(Some(500), Some(333), running));
+        assert!(needs_restart(Some(1000), Some(250), running));
+        assert!(needs_restart(Some(100), Some(500), running));
+        // Limite que entra ou sai de vigor pelo horário
+        assert!(needs_restart(None, Some(500), running));
+        assert!(needs_restart(Some(500), None, running));
+        assert!(!needs_restart(None, None, running));
+        // Recém-iniciado nunca é reiniciado
+        assert!(!needs_restart(Some(1000), Some(100), Duration::from_secs(5)));
+
+        let queue = DownloadQueue::default();
+        let started = Instant::now() - running;
+        for url in ["https://a.test/1", "https://a.test/2"] {
+            queue.mark_active(DownloadRequest::new(url.to_string(), None));
+            queue.rates.lock().unwrap().insert(url.to_string(), (Some(1000), started));
+        }
+        queue.mark_active(DownloadRequest::new("https://a.test/3".to_string(), None));
+        queue.set_rate("https://a.test/3", Some(1000));
+
+        queue.rebalance(Some(250));
+        assert_eq!(queue.take_stop_request("https://a.test/1"), Some(StopReason::Requeue));
+        assert_eq!(queue.take_stop_request("https://a.test/2"), Some(StopReason::Requeue));
+        assert_eq!(queue.take_stop_request("https://a.test/3"), None);
+
+        // Dentro do intervalo mínimo nada mais é reiniciado
+        queue.rates.lock().unwrap().insert("https://a.test/3".to_string(), (Some(1000), started));
+        queue.rebalance(Some(100));
+        assert_eq!(queue.take_stop_request("https://a.test/3"), None);
+    }
+
+    #[test]
+    fn late_stop_requests_do_not_reach_the_next_run() {
+        let queue = DownloadQueue::default();
+        let url = "https://a.test/1";
+        queue.mark_active(DownloadRequest::new(url.to_string(), None));
+
+        // O worker já leu o pedido de parada (nenhum) quando chega o cancelamento
+        assert_eq!(queue.take_stop_request(url), None);
+        assert!(queue.stop_active(url, StopReason::Cancel));
+        queue.remove_active(url, ActiveOutcome::Requeue);
+        assert!(!queue.stop_active(url, StopReason::Pause));
+
+        queue.mark_active(queue.get_next(&[]).unwrap());
+        assert_eq!(queue.stop_request(url), None);
+    }
+}
@@ -0,0 +1,128 @@
+// Histórico das URLs detectadas na área de transferência, da mais recente para a mais antiga
+
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecentUrl {
+    pub url: String,
+    // Como a URL foi reconhecida: regra do site, extrator do yt-dlp, programa de download
+    pub source: String,
+    pub detected_at: DateTime<Utc>,
+}
+
+// Buffer circular: ao passar da capacidade, a URL mais antiga sai
+#[derive(Default)]
+pub struct RecentUrls {
+    entries: Mutex<VecDeque<RecentUrl>>,
+}
+
+impl RecentUrls {
+    // Copiar de novo uma URL já listada só a traz para o topo
+    pub fn push(&self, url: &str, source: &str, capacity: usize) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|entry| entry.url != url);
+        entries.push_front(RecentUrl {
+            url: url.to_string(),
+            source: source.to_string(),
+            detected_at: Utc::now(),
+        });
+        entries.truncate(capacity.max(1));
+    }
+
+    pub fn list(&self) -> Vec<RecentUrl> {
+        self.entries.lock().unwrap().iter().cloned().collect()
+    }
+
+    pub fn latest(&self) -> Option<RecentUrl> {
+        self.entries.lock().unwrap().front().cloned()
+    }
+
+    pub fn remove(&self, url: &str) -> bool {
+        let mut entries = self.entries.lock().unwrap();
+        let before = entries.len();
+        entries.retain(|entry| entry.url != url);
+        entries.len() != before
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+fn url_regex() -> &'static Regex {
+    static URL_REGEX: OnceLock<Regex> = OnceLock::new();
+    URL_REGEX.get_or_init(|| Regex::new(r#"(?i)https?://[^\s<>"'`\[\]{}|\\^]+"#).unwrap())
+}
+
+// Todas as URLs http(s) do texto copiado, na ordem e sem repetições
+pub fn extract_urls(text: &str) -> Vec<String> {
+    let mut urls: Vec<String> = Vec::new();
+
+    for found in url_regex().find_iter(text) {
+        let url = trim_url(found.as_str());
+        if url.len() > "https://".len() && !urls.iter().any(|u| u == url) {
+            urls.push(url.to_string());
+        }
+    }
+
+    urls
+}
+
+// Pontuação no fim da frase não faz parte da URL; ")" só fica se tiver um "(" correspondente
+fn trim_url(mut url: &str) -> &str {
+    loop {
+        let trimmed = url.trim_end_matches(['.', ',', ';', ':', '!', '?']);
+        let trimmed = match trimmed.strip_suffix(')') {
+            Some(rest) if trimmed.matches('(').count() < trimmed.matches(')').count() => rest,
+            _ => trimmed,
+        };
+        if trimmed == url {
+            return url;
+        }
+        url = trimmed;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_every_url_from_text() {
+        let text = "Vejam https://youtu.be/abc, e também (https://vimeo.com/123).\n\
+                    https://youtu.be/abc de novo e https://pt.wikipedia.org/wiki/Rust_(linguagem)!";
+        assert_eq!(
+            extract_urls(text),
+            vec![
+                "https://youtu.be/abc",
+                "https://vimeo.com/123",
+                "https://pt.wikipedia.org/wiki/Rust_(linguagem)",
+            ]
+        );
+        assert_eq!(extract_urls("  https://example.com/v?id=1&t=2  "), vec!["https://example.com/v?id=1&t=2"]);
+        assert!(extract_urls("sem links aqui, nem http://").is_empty());
+    }
+
+    #[test]
+    fn keeps_the_newest_urls_first() {
+        let recent = RecentUrls::default();
+        recent.push("https://a.test/1", "regra", 3);
+        recent.push("https://a.test/2", "regra", 3);
+        recent.push("https://a.test/3", "regra", 3);
+        recent.push("https://a.test/1", "regra", 3);
+        recent.push("https://a.test/4", "regra", 3);
+
+        let urls: Vec<String> = recent.list().into_iter().map(|entry| entry.url).collect();
+        assert_eq!(urls, vec!["https://a.test/4", "https://a.test/1", "https://a.test/3"]);
+        assert_eq!(recent.latest().unwrap().url, "https://a.test/4");
+
+        assert!(recent.remove("https://a.test/1"));
+        assert!(!recent.remove("https://a.test/1"));
+        recent.clear();
+        assert!(recent.latest().is_none());
+    }
+}
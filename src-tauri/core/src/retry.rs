@@ -0,0 +1,40 @@
+// Novas tentativas automáticas para falhas transitórias
+
+use crate::errors::ErrorKind;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+// Espera máxima entre tentativas, por maior que seja o número de tentativas
+const MAX_BACKOFF_SECS: u64 = 3600;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    // Total de tentativas, incluindo a primeira; 1 desativa as novas tentativas
+    pub max_attempts: u32,
+    // Espera antes da segunda tentativa; dobra a cada nova falha
+    pub backoff_base_secs: u64,
+    pub retryable: Vec<ErrorKind>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff_base_secs: 30,
+            retryable: vec![ErrorKind::RateLimited, ErrorKind::Network, ErrorKind::Fragment],
+        }
+    }
+}
+
+impl RetryPolicy {
+    // `attempts` é o número de tentativas já feitas, incluindo a que acabou de falhar
+    pub fn should_retry(&self, kind: ErrorKind, attempts: u32) -> bool {
+        attempts < self.max_attempts && self.retryable.contains(&kind)
+    }
+
+    pub fn delay(&self, attempts: u32) -> Duration {
+        let factor = 2u64.saturating_pow(attempts.saturating_sub(1));
+        Duration::from_secs(self.backoff_base_secs.saturating_mul(factor).min(MAX_BACKOFF_SECS))
+    }
+}
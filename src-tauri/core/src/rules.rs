@@ -0,0 +1,133 @@
+// Regras por site: quais URLs são aceitas e com quais opções
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::backends::BackendKind;
+use crate::output::OutputOptions;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleAction {
+    Allow,
+    Deny,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SiteRule {
+    // "youtube.com" (inclui subdomínios) ou curinga como "*.cdn.example.com"
+    pub host: String,
+    // Regex opcional aplicada ao caminho + query (ex.: "^/watch")
+    #[serde(default)]
+    pub path: Option<String>,
+    pub action: RuleAction,
+    #[serde(default)]
+    pub profile: Option<String>,
+    // Pasta, modelo do nome, legendas e pós-processamento dos downloads do site
+    #[serde(flatten)]
+    pub output: OutputOptions,
+    // None segue a configuração global de download automático
+    #[serde(default)]
+    pub auto_download: Option<bool>,
+    // None escolhe o programa pela URL (ver backends::detect)
+    #[serde(default)]
+    pub backend: Option<BackendKind>,
+}
+
+impl SiteRule {
+    pub fn allow(host: &str) -> Self {
+        Self {
+            host: host.to_string(),
+            path: None,
+            action: RuleAction::Allow,
+            profile: None,
+            output: OutputOptions::default(),
+            auto_download: None,
+            backend: None,
+        }
+    }
+
+    fn matches(&self, host: &str, path: &str) -> bool {
+        host_matches(&self.host, host) && self.path_matches(path)
+    }
+
+    fn path_matches(&self, path: &str) -> bool {
+        match self.path.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+            // Regex inválida nunca casa, para não liberar URLs por engano
+            Some(pattern) => Regex::new(pattern).map(|re| re.is_match(path)).unwrap_or(false),
+            None => true,
+        }
+    }
+}
+
+pub fn default_rules() -> Vec<SiteRule> {
+    [
+        "pornhub.com",
+        "xvideos.com",
+        "xhamster.com",
+        "youtube.com",
+        "youtu.be",
+        "vimeo.com",
+        "dailymotion.com",
+        "twitch.tv",
+        "tiktok.com",
+        "instagram.com",
+        "facebook.com",
+        "twitter.com",
+        "x.com",
+    ]
+    .iter()
+    .map(|host| SiteRule::allow(host))
+    .collect()
+}
+
+// Domínio simples casa com o host e seus subdomínios; "*" casa com qualquer sequência
+pub fn host_matches(pattern: &str, host: &str) -> bool {
+    let pattern = pattern.trim().trim_end_matches('.').to_lowercase();
+
+    if pattern.is_empty() {
+        return false;
+    }
+
+    if !pattern.contains('*') {
+        return host == pattern || host.ends_with(&format!(".{}", pattern));
+    }
+
+    let regex = pattern
+        .split('*')
+        .map(regex::escape)
+        .collect::<Vec<_>>()
+        .join(".*");
+
+    Regex::new(&format!("^{}$", regex))
+        .map(|re| re.is_match(host))
+        .unwrap_or(false)
+}
+
+// Primeira regra que casa com a URL; None se nenhuma casar ou se a URL não for http(s)
+pub fn find_rule<'a>(rules: &'a [SiteRule], url: &str) -> Option<&'a SiteRule> {
+    let parsed = Url::parse(url.trim()).ok()?;
+
+    if parsed.scheme() != "http" && parsed.scheme() != "https" {
+        return None;
+    }
+
+    let host = parsed.host_str()?.trim_end_matches('.').to_lowercase();
+    let path = match parsed.query() {
+        Some(query) => format!("{}?{}", parsed.path(), query),
+        None => parsed.path().to_string(),
+    };
+
+    rules.iter().find(|rule| rule.matches(&host, &path))
+}
+
+// Regra de permissão aplicável à URL (regras de bloqueio retornam None)
+pub fn allowed_rule<'a>(rules: &'a [SiteRule], url: &str) -> Option<&'a SiteRule> {
+    find_rule(rules, url).filter(|rule| rule.action == RuleAction::Allow)
+}
+
+// URL de vídeo segundo as regras (sem consultar o yt-dlp)
+pub fn is_video_url(rules: &[SiteRule], url: &str) -> bool {
+    allowed_rule(rules, url).is_some()
+}
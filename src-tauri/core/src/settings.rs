@@ -0,0 +1,342 @@
+// Configurações do aplicativo, salvas em settings.json
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::auto_download::AutoDownloadOptions;
+use crate::backends::BackendOptions;
+use crate::bandwidth::BandwidthOptions;
+use crate::limits::{self, HostLimit};
+use crate::output::{PostProcessor, SubtitleOptions};
+use crate::playlist::PlaylistOptions;
+use crate::retry::RetryPolicy;
+use crate::rules::{self, SiteRule};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    // Executável do yt-dlp: nome no PATH ou caminho completo
+    pub ytdlp_path: String,
+    pub download_path: String,
+    pub filename_template: String,
+    // Padrões de cada download; regras de sites e pedidos podem trocar
+    pub subtitles: SubtitleOptions,
+    pub post_processors: Vec<PostProcessor>,
+    pub max_concurrent: usize,
+    pub enable_notifications: bool,
+    pub enable_sound: bool,
+    pub check_duplicates: bool,
+    pub watch_clipboard: bool,
+    pub watch_primary_selection: bool,
+    // Quantas URLs detectadas ficam no histórico do menu do systray
+    pub clipboard_history_size: usize,
+    pub format_profiles: Vec<FormatProfile>,
+    pub default_profile: String,
+    pub playlist: PlaylistOptions,
+    pub auto_download: AutoDownloadOptions,
+    // Avaliadas em ordem; a primeira que casar decide
+    pub site_rules: Vec<SiteRule>,
+    // Consulta o yt-dlp para URLs que nenhuma regra reconhece
+    pub ytdlp_detection: bool,
+    pub extractor_cache_ttl_hours: u64,
+    pub retry: RetryPolicy,
+    // Limites por site aplicados além do max_concurrent global
+    pub host_limits: Vec<HostLimit>,
+    pub bandwidth: BandwidthOptions,
+    pub api: ApiOptions,
+    // youtube-dl, gallery-dl e HTTP direto, escolhidos por URL
+    pub backends: BackendOptions,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+// API HTTP local para extensões de navegador e scripts
+pub struct ApiOptions {
+    pub enabled: bool,
+    pub port: u16,
+    // Gerado automaticamente quando vazio
+    pub token: String,
+}
+
+impl Default for ApiOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 9468,
+            token: String::new(),
+        }
+    }
+}
+
+pub fn generate_token() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+// Perfil de qualidade/formato traduzido em argumentos do yt-dlp
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormatProfile {
+    pub id: String,
+    pub name: String,
+    pub format: Option<String>,
+    pub sort: Option<String>,
+    pub merge_output_format: Option<String>,
+    pub audio_format: Option<String>,
+}
+
+impl FormatProfile {
+    pub fn new(id: &str, name: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            format: None,
+            sort: None,
+            merge_output_format: None,
+            audio_format: None,
+        }
+    }
+
+    pub fn defaults() -> Vec<FormatProfile> {
+        vec![
+            FormatProfile {
+                format: Some("bv*+ba/b".to_string()),
+                ..Self::new("best", "Melhor vídeo + áudio")
+            },
+            FormatProfile {
+                format: Some("bv*+ba/b".to_string()),
+                sort: Some("res:1080,ext:mp4:m4a".to_string()),
+                merge_output_format: Some("mp4".to_string()),
+                ..Self::new("1080p-mp4", "1080p MP4")
+            },
+            FormatProfile {
+                format: Some("bv*[height<=720]+ba/b[height<=720]".to_string()),
+                ..Self::new("720p", "Até 720p")
+            },
+            FormatProfile {
+                format: Some("ba/b".to_string()),
+                audio_format: Some("mp3".to_string()),
+                ..Self::new("audio-mp3", "Apenas áudio (MP3)")
+            },
+        ]
+    }
+
+    pub fn args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+
+        if let Some(format) = &self.format {
+            args.push("-f".to_string());
+            args.push(format.clone());
+        }
+        if let Some(sort) = &self.sort {
+            args.push("-S".to_string());
+            args.push(sort.clone());
+        }
+        if let Some(merge) = &self.merge_output_format {
+            args.push("--merge-output-format".to_string());
+            args.push(merge.clone());
+        }
+        if let Some(audio) = &self.audio_format {
+            args.push("-x".to_string());
+            args.push("--audio-format".to_string());
+            args.push(audio.clone());
+        }
+
+        args
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            ytdlp_path: "yt-dlp".to_string(),
+            download_path: default_download_dir(true).to_string_lossy().into_owned(),
+            filename_template: "%(title)s.%(ext)s".to_string(),
+            subtitles: SubtitleOptions::default(),
+            post_processors: Vec::new(),
+            max_concurrent: 3,
+            enable_notifications: true,
+            enable_sound: true,
+            check_duplicates: true,
+            watch_clipboard: true,
+            watch_primary_selection: false,
+            clipboard_history_size: 20,
+            format_profiles: FormatProfile::defaults(),
+            default_profile: "best".to_string(),
+            playlist: PlaylistOptions::default(),
+            auto_download: AutoDownloadOptions::default(),
+            site_rules: rules::default_rules(),
+            ytdlp_detection: false,
+            extractor_cache_ttl_hours: 24,
+            retry: RetryPolicy::default(),
+            host_limits: limits::default_limits(),
+            bandwidth: BandwidthOptions::default(),
+            api: ApiOptions::default(),
+            backends: BackendOptions::default(),
+        }
+    }
+}
+
+impl Settings {
+    // Perfil pedido ou, se não existir, o perfil padrão
+    pub fn profile(&self, id: Option<&str>) -> Option<&FormatProfile> {
+        let find = |id: &str| self.format_profiles.iter().find(|p| p.id == id);
+        id.and_then(find).or_else(|| find(&self.default_profile))
+    }
+}
+
+// Padrão das versões antigas, que só existia na máquina de quem o escreveu
+const LEGACY_DOWNLOAD_PATH: &str = "C:\\Users\\Lucas\\Desktop";
+
+// Pasta Downloads do sistema (ou da pasta pessoal), com a subpasta "ytdl-monitor" opcional
+pub fn default_download_dir(subfolder: bool) -> PathBuf {
+    let dir = dirs::download_dir()
+        .or_else(|| dirs::home_dir().map(|home| home.join("Downloads")))
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    if subfolder {
+        dir.join("ytdl-monitor")
+    } else {
+        dir
+    }
+}
+
+// Pasta de configuração do aplicativo (settings.json, history.json, queue.json)
+pub fn app_config_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("ytdl-monitor")
+}
+
+// Gerenciador de configurações
+pub struct SettingsManager {
+    settings: Mutex<Arc<Settings>>,
+    config_path: PathBuf,
+}
+
+impl SettingsManager {
+    pub fn open(config_dir: &Path) -> Self {
+        fs::create_dir_all(config_dir).ok();
+        
+        let config_path = config_dir.join("settings.json");
+        let settings = Self::load_from_file(&config_path);
+        
+        Self {
+            settings: Mutex::new(Arc::new(settings)),
+            config_path,
+        }
+    }
+
+    fn load_from_file(path: &PathBuf) -> Settings {
+        let mut settings: Settings = fs::read_to_string(path)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+        migrate_download_path(&mut settings);
+        settings
+    }
+
+    fn save(&self) {
+        let settings = self.get();
+        if let Ok(json) = serde_json::to_string_pretty(settings.as_ref()) {
+            fs::write(&self.config_path, json).ok();
+        }
+    }
+
+    // Cópia barata: as configurações só são substituídas por inteiro ao salvar
+    pub fn get(&self) -> Arc<Settings> {
+        self.settings.lock().unwrap().clone()
+    }
+
+    pub fn update(&self, new_settings: Settings) {
+        *self.settings.lock().unwrap() = Arc::new(new_settings);
+        self.save();
+    }
+}
+
+// settings.json com o padrão antigo passa para a pasta Downloads, a menos que a pasta
+// antiga exista de verdade nesta máquina
+fn migrate_download_path(settings: &mut Settings) {
+    if settings.download_path == LEGACY_DOWNLOAD_PATH && !Path::new(LEGACY_DOWNLOAD_PATH).is_dir() {
+        settings.download_path = default_download_dir(true).to_string_lossy().into_owned();
+    }
+}
+
+// Valor de uma configuração pelo caminho com pontos (ex.: "bandwidth.limit_kib");
+// sem chave, todas as configurações
+pub fn get_value(settings: &Settings, key: Option<&str>) -> Option<Value> {
+    let value = serde_json::to_value(settings).ok()?;
+    match key {
+        Some(key) => value.pointer(&json_pointer(key)).cloned(),
+        None => Some(value),
+    }
+}
+
+// Troca um valor e valida o resultado convertendo de volta para Settings
+pub fn set_value(settings: &Settings, key: &str, new_value: Value) -> Result<Settings, String> {
+    let mut value = serde_json::to_value(settings).map_err(|e| e.to_string())?;
+    let slot = value
+        .pointer_mut(&json_pointer(key))
+        .ok_or_else(|| format!("configuração desconhecida: {}", key))?;
+    *slot = new_value;
+    serde_json::from_value(value).map_err(|e| format!("valor inválido para {}: {}", key, e))
+}
+
+// Caminhos dos programas que o app executa
+pub fn executables(settings: &Settings) -> [&str; 3] {
+    [
+        &settings.ytdlp_path,
+        &settings.backends.youtube_dl_path,
+        &settings.backends.gallery_dl_path,
+    ]
+}
+
+fn json_pointer(key: &str) -> String {
+    key.split('.').map(|part| format!("/{}", part)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_nested_values() {
+        let settings = Settings::default();
+        assert_eq!(get_value(&settings, Some("max_concurrent")), Some(Value::from(3)));
+        assert_eq!(get_value(&settings, Some("bandwidth.limit_kib")), Some(Value::from(0)));
+        assert_eq!(get_value(&settings, Some("nope")), None);
+    }
+
+    #[test]
+    fn download_dir_defaults_to_the_downloads_folder() {
+        let dir = default_download_dir(false);
+        assert_eq!(default_download_dir(true), dir.join("ytdl-monitor"));
+        assert_eq!(Settings::default().download_path, dir.join("ytdl-monitor").to_string_lossy());
+
+        let mut settings = Settings {
+            download_path: LEGACY_DOWNLOAD_PATH.to_string(),
+            ..Settings::default()
+        };
+        if !Path::new(LEGACY_DOWNLOAD_PATH).is_dir() {
+            migrate_download_path(&mut settings);
+            assert_eq!(settings.download_path, Settings::default().download_path);
+        }
+
+        let mut custom = Settings {
+            download_path: "/srv/videos".to_string(),
+            ..Settings::default()
+        };
+        migrate_download_path(&mut custom);
+        assert_eq!(custom.download_path, "/srv/videos");
+    }
+
+    #[test]
+    fn sets_and_validates_values() {
+        let settings = set_value(&Settings::default(), "bandwidth.limit_kib", Value::from(512)).unwrap();
+        assert_eq!(settings.bandwidth.limit_kib, 512);
+
+        assert!(set_value(&settings, "nope", Value::from(1)).is_err());
+        assert!(set_value(&settings, "max_concurrent", Value::from("três")).is_err());
+    }
+}
@@ -0,0 +1,292 @@
+// Validação das configurações antes de salvar: pasta de destino, modelo do nome do arquivo
+// e limites dos campos numéricos. Os erros apontam o campo pelo mesmo caminho com pontos
+// usado em `ytdl-monitor config` (ex.: "retry.max_attempts")
+
+use serde::Serialize;
+use std::fs;
+use std::path::{Component, Path};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+use crate::output::OutputOptions;
+use crate::settings::Settings;
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+// "campo: mensagem", uma por linha, para a CLI e a API
+pub fn describe(errors: &[FieldError]) -> String {
+    errors
+        .iter()
+        .map(|error| format!("{}: {}", error.field, error.message))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Corrige o que dá para corrigir (espaços, valores vazios) e devolve os erros restantes.
+// Cria a pasta de destino se ainda não existir
+pub fn validate(settings: &mut Settings) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+
+    settings.download_path = settings.download_path.trim().to_string();
+    settings.filename_template = settings.filename_template.trim().to_string();
+    settings.ytdlp_path = settings.ytdlp_path.trim().to_string();
+    if settings.ytdlp_path.is_empty() {
+        settings.ytdlp_path = "yt-dlp".to_string();
+    }
+    if settings.playlist.newest == Some(0) {
+        settings.playlist.newest = None;
+    }
+    if !settings.format_profiles.iter().any(|p| p.id == settings.default_profile) {
+        errors.push(FieldError::new("default_profile", "Perfil não encontrado"));
+    }
+
+    if let Err(message) = check_download_dir(Path::new(&settings.download_path)) {
+        errors.push(FieldError::new("download_path", message));
+    }
+    if let Err(message) = check_template(&settings.filename_template) {
+        errors.push(FieldError::new("filename_template", message));
+    }
+
+    let mut bound = |field: &str, value: u64, min: u64, max: u64| {
+        if value < min || value > max {
+            errors.push(FieldError::new(field, format!("Use um valor entre {} e {}", min, max)));
+        }
+    };
+    bound("max_concurrent", settings.max_concurrent as u64, 1, 10);
+    bound("clipboard_history_size", settings.clipboard_history_size as u64, 1, 100);
+    bound("auto_download.delay_secs", settings.auto_download.delay_secs, 0, 60);
+    bound("extractor_cache_ttl_hours", settings.extractor_cache_ttl_hours, 1, 24 * 365);
+    bound("retry.max_attempts", settings.retry.max_attempts as u64, 1, 20);
+    bound("retry.backoff_base_secs", settings.retry.backoff_base_secs, 1, 24 * 3600);
+    bound("api.port", settings.api.port as u64, 1024, 65535);
+
+    errors
+}
+
+// Pasta e modelo próprios de um pedido (janela, API local): as mesmas regras das configurações.
+// Campos vazios seguem a regra do site ou as configurações, já validadas
+pub fn validate_output(output: &OutputOptions) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    fn given(value: &Option<String>) -> Option<&str> {
+        value.as_deref().map(str::trim).filter(|value| !value.is_empty())
+    }
+
+    if let Some(dir) = given(&output.output_dir) {
+        if let Err(message) = check_download_dir(Path::new(dir)) {
+            errors.push(FieldError::new("output_dir", message));
+        }
+    }
+    if let Some(template) = given(&output.filename_template) {
+        if let Err(message) = check_template(template) {
+            errors.push(FieldError::new("filename_template", message));
+        }
+    }
+    errors
+}
+
+// Caminho completo, criado se preciso, e com permissão de escrita
+fn check_download_dir(dir: &Path) -> Result<(), String> {
+    if dir.as_os_str().is_empty() {
+        return Err("Informe a pasta de destino".to_string());
+    }
+    if !dir.is_absolute() {
+        return Err("Use o caminho completo da pasta".to_string());
+    }
+    if dir.exists() && !dir.is_dir() {
+        return Err("O caminho existe, mas não é uma pasta".to_string());
+    }
+
+    fs::create_dir_all(dir).map_err(|e| format!("Não foi possível criar a pasta: {}", e))?;
+
+    // Permissões de pasta variam por sistema; só gravando um arquivo dá para ter certeza
+    let probe = dir.join(format!(".ytdl-monitor-{}.tmp", uuid::Uuid::new_v4().simple()));
+    fs::write(&probe, b"").map_err(|e| format!("Sem permissão de escrita na pasta: {}", e))?;
+    let _ = fs::remove_file(&probe);
+    Ok(())
+}
+
+fn check_template(template: &str) -> Result<(), String> {
+    if template.is_empty() {
+        return Err("Informe o modelo do nome do arquivo".to_string());
+    }
+    if !template.contains("%(ext)s") {
+        return Err("O modelo precisa terminar com a extensão, ex.: %(title)s.%(ext)s".to_string());
+    }
+    if Path::new(template).is_absolute() {
+        return Err("O modelo é relativo à pasta de destino; escolha a pasta no campo acima".to_string());
+    }
+    if Path::new(template).components().any(|part| part == Component::ParentDir) {
+        return Err("O modelo não pode sair da pasta de destino (..)".to_string());
+    }
+    Ok(())
+}
+
+// Metadados de exemplo para montar o nome sem acessar a rede
+const SAMPLE_INFO: &str = r#"{
+    "_type": "video",
+    "id": "dQw4w9WgXcQ",
+    "title": "Título do Vídeo",
+    "ext": "mp4",
+    "url": "https://example.com/video.mp4",
+    "webpage_url": "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+    "extractor": "youtube",
+    "extractor_key": "Youtube",
+    "uploader": "Nome do Canal",
+    "channel": "Nome do Canal",
+    "upload_date": "20240131",
+    "duration": 213,
+    "height": 1080,
+    "resolution": "1920x1080",
+    "playlist_index": 1,
+    "playlist_title": "Nome da Playlist"
+}"#;
+
+// Nome que o yt-dlp geraria com o modelo (--print filename --simulate), a partir dos
+// metadados de exemplo. None quando o yt-dlp não está disponível para conferir
+pub async fn preview_filename(ytdlp: &str, template: &str) -> Result<Option<String>, String> {
+    let info_path = std::env::temp_dir().join(format!(
+        "ytdl-monitor-preview-{}.info.json",
+        uuid::Uuid::new_v4().simple()
+    ));
+    if tokio::fs::write(&info_path, SAMPLE_INFO).await.is_err() {
+        return Ok(None);
+    }
+
+    let mut command = Command::new(ytdlp);
+    command
+        .arg("--ignore-config")
+        .arg("--no-warnings")
+        .arg("--simulate")
+        .arg("--print")
+        .arg("filename")
+        .arg("-o")
+        .arg(template)
+        .arg("--load-info-json")
+        .arg(&info_path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    #[cfg(target_os = "windows")]
+    command.creation_flags(0x08000000);
+
+    let output = tokio::time::timeout(Duration::from_secs(15), command.output()).await;
+    let _ = tokio::fs::remove_file(&info_path).await;
+
+    let output = match output {
+        Ok(Ok(output)) => output,
+        // Sem yt-dlp (ou travado) não há como conferir; as outras verificações já passaram
+        _ => return Ok(None),
+    };
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = stderr
+            .lines()
+            .rev()
+            .find_map(|line| line.trim().strip_prefix("ERROR:"))
+            .map(|message| format!("Modelo inválido: {}", message.trim()))
+            .unwrap_or_else(|| "Modelo inválido".to_string());
+        return Err(message);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn valid_settings(name: &str) -> Settings {
+        Settings {
+            download_path: std::env::temp_dir()
+                .join(format!("ytdl-validation-{}-{}", name, std::process::id()))
+                .join("videos")
+                .to_string_lossy()
+                .into_owned(),
+            ..Settings::default()
+        }
+    }
+
+    #[test]
+    fn accepts_and_sanitizes_valid_settings() {
+        let mut settings = valid_settings("ok");
+        settings.download_path = format!("  {}  ", settings.download_path);
+        settings.ytdlp_path = " ".to_string();
+        settings.playlist.newest = Some(0);
+
+        assert_eq!(validate(&mut settings), vec![]);
+        assert!(Path::new(&settings.download_path).is_dir());
+        assert_eq!(settings.ytdlp_path, "yt-dlp");
+        assert_eq!(settings.playlist.newest, None);
+        let _ = fs::remove_dir_all(Path::new(&settings.download_path).parent().unwrap());
+    }
+
+    #[test]
+    fn reports_each_invalid_field() {
+        let mut settings = valid_settings("errors");
+        settings.download_path = "videos".to_string();
+        settings.filename_template = "%(title)s".to_string();
+        settings.max_concurrent = 0;
+        settings.api.port = 80;
+
+        let fields: Vec<String> = validate(&mut settings).into_iter().map(|e| e.field).collect();
+        assert_eq!(fields, ["download_path", "filename_template", "max_concurrent", "api.port"]);
+    }
+
+    #[test]
+    fn rejects_a_file_as_download_dir() {
+        let mut settings = valid_settings("file");
+        let file = Path::new(&settings.download_path).with_file_name("arquivo.txt");
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(&file, "x").unwrap();
+        settings.download_path = file.to_string_lossy().into_owned();
+
+        let errors = validate(&mut settings);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "download_path");
+        let _ = fs::remove_dir_all(file.parent().unwrap());
+    }
+
+    #[test]
+    fn request_overrides_follow_the_same_rules() {
+        assert_eq!(validate_output(&OutputOptions::default()), vec![]);
+
+        let fields = |output_dir: &str, template: &str| -> Vec<String> {
+            let output = OutputOptions {
+                output_dir: Some(output_dir.to_string()),
+                filename_template: Some(template.to_string()),
+                ..OutputOptions::default()
+            };
+            validate_output(&output).into_iter().map(|e| e.field).collect()
+        };
+        assert_eq!(fields("videos", "/etc/%(id)s.%(ext)s"), ["output_dir", "filename_template"]);
+        assert_eq!(fields(" ", "../../%(id)s.%(ext)s"), ["filename_template"]);
+        assert_eq!(fields("", "%(title)s"), ["filename_template"]);
+
+        let dir = valid_settings("override").download_path;
+        assert_eq!(fields(&dir, "%(uploader)s/%(title)s.%(ext)s"), Vec::<String>::new());
+        let _ = fs::remove_dir_all(Path::new(&dir).parent().unwrap());
+    }
+}
@@ -0,0 +1,507 @@
+// Testes de ponta a ponta da fila com um yt-dlp falso (script sh), sem acesso à rede
+#![cfg(unix)]
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use ytdl_monitor_core::errors::ErrorKind;
+use ytdl_monitor_core::output::OutputOptions;
+use ytdl_monitor_core::queue::{DownloadProgress, QuitMode};
+use ytdl_monitor_core::rules::SiteRule;
+use ytdl_monitor_core::{Core, DownloadStatus, EventSink, HistoryStatus, QueueOutcome};
+
+// Imita a saída do yt-dlp com os --print e o --progress-template usados pelo downloader
+const FAKE_YTDLP: &str = r#"#!/bin/sh
+output=""
+url=""
+simulate=""
+info=""
+flat=""
+playlist_end=""
+while [ $# -gt 0 ]; do
+    case "$1" in
+        -o) output="$2"; shift ;;
+        --simulate) simulate=1 ;;
+        --flat-playlist) flat=1 ;;
+        --playlist-end) playlist_end="$2"; shift ;;
+        --load-info-json) info="$2"; shift ;;
+        http*) url="$1" ;;
+    esac
+    shift
+done
+id="${url##*/}"
+id="${id%%\?*}"
+
+# Prévia do modelo do nome do arquivo com os metadados de exemplo
+if [ -n "$info" ]; then
+    case "$output" in
+        *"%(invalid"*)
+            echo "ERROR: invalid output template" >&2
+            exit 1 ;;
+    esac
+    printf '%s\n' "$output" | sed 's/%(title)s/Título do Vídeo/g; s/%(ext)s/mp4/g'
+    exit 0
+fi
+
+# Expansão de playlist: até 5 vídeos, ou os N primeiros com --playlist-end
+if [ -n "$flat" ]; then
+    echo "$playlist_end" > "$(dirname "$0")/playlist-end"
+    case "$url" in
+        *list=*) ;;
+        *) echo '{"_type": "video"}'; exit 0 ;;
+    esac
+    count="${playlist_end:-5}"
+    printf '{"_type": "playlist", "title": "Lista", "entries": ['
+    i=1
+    while [ "$i" -le "$count" ]; do
+        [ "$i" -gt 1 ] && printf ', '
+        printf '{"url": "https://example.test/item%s", "title": "Item %s"}' "$i" "$i"
+        i=$((i + 1))
+    done
+    echo ']}'
+    exit 0
+fi
+
+# Consulta do id para a detecção de duplicatas
+if [ -n "$simulate" ]; then
+    printf 'Generic\t%s\n' "$id"
+    exit 0
+fi
+
+case "$url" in
+    *fail*)
+        echo "ERROR: [generic] Unable to download webpage: HTTP Error 403: Forbidden" >&2
+        exit 1 ;;
+    *slow*)
+        sleep 0.3 ;;
+esac
+
+# Sem -o absoluto o arquivo iria para a pasta atual (a do crate); os testes só gravam em temp_dir
+case "$output" in
+    /*) ;;
+    *)
+        echo "ERROR: output template must be an absolute path: $output" >&2
+        exit 1 ;;
+esac
+
+dir="$(dirname "$output")"
+mkdir -p "$dir"
+echo "[ytdl-info]{\"id\": \"$id\", \"extractor_key\": \"Generic\", \"title\": \"Vídeo $id\", \"uploader\": null, \"duration\": 10}"
+echo "[ytdl-progress]2|4|NA|1024|1|NA|NA" >&2
+echo "[ytdl-progress]4|4|NA|1024|0|NA|NA" >&2
+printf video > "$dir/$id.mp4"
+echo "[ytdl-file]$dir/$id.mp4"
+"#;
+
+#[derive(Default)]
+struct Recorder {
+    notifications: Mutex<Vec<String>>,
+    progress: Mutex<Vec<(String, DownloadProgress)>>,
+    max_active: Mutex<usize>,
+}
+
+impl EventSink for Recorder {
+    fn notify(&self, title: &str, _body: &str) {
+        self.notifications.lock().unwrap().push(title.to_string());
+    }
+
+    fn status_changed(&self, status: &DownloadStatus) {
+        let mut max = self.max_active.lock().unwrap();
+        *max = (*max).max(status.active);
+    }
+
+    fn progress(&self, url: &str, progress: &DownloadProgress) {
+        self.progress.lock().unwrap().push((url.to_string(), progress.clone()));
+    }
+}
+
+// Pasta temporária própria de cada teste, com o script do yt-dlp falso
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ytdl-core-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    let script = dir.join("yt-dlp");
+    fs::write(&script, FAKE_YTDLP).unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+    dir
+}
+
+fn start(dir: &Path, ytdlp: &str, max_concurrent: usize) -> (Arc<Core>, Arc<Recorder>) {
+    let recorder = Arc::new(Recorder::default());
+    let core = Core::open(dir, recorder.clone());
+
+    let mut settings = core.settings.get().as_ref().clone();
+    settings.ytdlp_path = ytdlp.to_string();
+    settings.download_path = dir.join("downloads").to_string_lossy().into_owned();
+    settings.filename_template = "%(id)s.%(ext)s".to_string();
+    settings.max_concurrent = max_concurrent;
+    settings.enable_sound = false;
+    settings.retry.max_attempts = 1;
+    settings.playlist.expand = false;
+    settings.host_limits.clear();
+    core.update_settings(settings);
+
+    tokio::spawn(core.clone().run_scheduler());
+    (core, recorder)
+}
+
+async fn wait_until(mut condition: impl FnMut() -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !condition() {
+        assert!(Instant::now() < deadline, "tempo esgotado esperando a fila");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+}
+
+fn finished(core: &Core, count: usize) -> bool {
+    core.history.entries().len() >= count && core.queue.active_count() == 0
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn successful_download_is_recorded_in_history() {
+    let dir = test_dir("success");
+    let (core, recorder) = start(&dir, &dir.join("yt-dlp").to_string_lossy(), 3);
+
+    let url = "https://example.test/abc123";
+    assert_eq!(core.queue_download(url, None), QueueOutcome::Queued);
+    wait_until(|| finished(&core, 1)).await;
+
+    let entries = core.history.entries();
+    let entry = &entries[0];
+    assert_eq!(entry.status, HistoryStatus::Completed);
+    assert_eq!(entry.video_id.as_deref(), Some("abc123"));
+    assert_eq!(entry.title.as_deref(), Some("Vídeo abc123"));
+    assert_eq!(entry.file_size, Some(5));
+    assert!(dir.join("downloads").join("abc123.mp4").exists());
+
+    let percents: Vec<f64> = recorder
+        .progress
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(progress_url, _)| progress_url == url)
+        .filter_map(|(_, progress)| progress.percent)
+        .collect();
+    assert_eq!(percents, vec![50.0, 100.0]);
+    assert!(recorder.notifications.lock().unwrap().contains(&"Download Concluído".to_string()));
+
+    // Duplicado pelo histórico
+    assert_eq!(core.queue_download(url, None), QueueOutcome::AlreadyDownloaded);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn failed_download_is_classified() {
+    let dir = test_dir("failure");
+    let (core, _) = start(&dir, &dir.join("yt-dlp").to_string_lossy(), 3);
+
+    let url = "https://example.test/fail";
+    core.queue_download(url, None);
+    wait_until(|| finished(&core, 1)).await;
+
+    let entry = &core.history.entries()[0];
+    assert_eq!(entry.status, HistoryStatus::Failed);
+    assert_eq!(entry.error_kind, Some(ErrorKind::Forbidden));
+
+    let status = core.queue.get_status();
+    assert_eq!(status.failed, 1);
+    let item = status.downloads.iter().find(|item| item.url == url).unwrap();
+    assert_eq!(item.status, "failed");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn missing_binary_is_reported() {
+    let dir = test_dir("missing");
+    let (core, _) = start(&dir, &dir.join("nao-existe").to_string_lossy(), 3);
+
+    core.queue_download("https://example.test/abc", None);
+    wait_until(|| finished(&core, 1)).await;
+
+    assert_eq!(core.history.entries()[0].error_kind, Some(ErrorKind::YtdlpMissing));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn queue_respects_max_concurrent() {
+    let dir = test_dir("concurrency");
+    let (core, recorder) = start(&dir, &dir.join("yt-dlp").to_string_lossy(), 2);
+
+    for index in 0..4 {
+        let url = format!("https://example.test/slow{}", index);
+        assert_eq!(core.queue_download(&url, None), QueueOutcome::Queued);
+    }
+    assert_eq!(core.queue_download("https://example.test/slow0", None), QueueOutcome::AlreadyQueued);
+
+    wait_until(|| finished(&core, 4)).await;
+
+    assert_eq!(*recorder.max_active.lock().unwrap(), 2);
+    assert!(core
+        .history
+        .entries()
+        .iter()
+        .all(|entry| entry.status == HistoryStatus::Completed));
+}
+
+// Servidor HTTP local que responde qualquer pedido com o mesmo conteúdo
+async fn serve_file(body: &'static [u8]) -> u16 {
+    serve_files(vec![("/", body)]).await
+}
+
+// Corpo escolhido pelo início do caminho pedido
+async fn serve_files(files: Vec<(&'static str, &'static [u8])>) -> u16 {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut buffer = [0; 4096];
+            let read = stream.read(&mut buffer).await.unwrap_or(0);
+            let request = String::from_utf8_lossy(&buffer[..read]);
+            let path = request.split_whitespace().nth(1).unwrap_or("/");
+            let body = files
+                .iter()
+                .find(|(prefix, _)| path.starts_with(prefix))
+                .map(|(_, body)| *body)
+                .unwrap_or(b"");
+            let head = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: video/mp4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            );
+            let _ = stream.write_all(head.as_bytes()).await;
+            let _ = stream.write_all(body).await;
+        }
+    });
+    port
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn direct_media_link_is_downloaded_over_http() {
+    let dir = test_dir("http");
+    // yt-dlp ausente: o link direto não pode depender dele
+    let (core, _) = start(&dir, &dir.join("nao-existe").to_string_lossy(), 3);
+    let port = serve_file(b"conteudo do video").await;
+
+    let url = format!("http://127.0.0.1:{}/media/Meu%20Clipe.mp4", port);
+    assert_eq!(core.queue_download(&url, None), QueueOutcome::Queued);
+    wait_until(|| finished(&core, 1)).await;
+
+    let entry = &core.history.entries()[0];
+    assert_eq!(entry.status, HistoryStatus::Completed, "{:?}", entry.error);
+    assert_eq!(entry.title.as_deref(), Some("Meu Clipe"));
+    assert_eq!(entry.file_size, Some(17));
+    assert_eq!(fs::read(dir.join("downloads").join("Meu Clipe.mp4")).unwrap(), b"conteudo do video");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn direct_links_with_the_same_file_name_are_both_downloaded() {
+    let dir = test_dir("http-names");
+    let (core, _) = start(&dir, &dir.join("nao-existe").to_string_lossy(), 1);
+    let port = serve_files(vec![("/a/", b"primeiro"), ("/b/", b"segundo")]).await;
+
+    core.queue_download(&format!("http://127.0.0.1:{}/a/video.mp4", port), None);
+    wait_until(|| finished(&core, 1)).await;
+    core.queue_download(&format!("http://127.0.0.1:{}/b/video.mp4", port), None);
+    wait_until(|| finished(&core, 2)).await;
+
+    let downloads = dir.join("downloads");
+    assert_eq!(fs::read(downloads.join("video.mp4")).unwrap(), b"primeiro");
+    assert_eq!(fs::read(downloads.join("video (1).mp4")).unwrap(), b"segundo");
+
+    // Cada entrada do histórico aponta para o próprio arquivo
+    let mut paths: Vec<String> = core.history.entries().into_iter().filter_map(|e| e.file_path).collect();
+    paths.sort();
+    assert_eq!(
+        paths,
+        [downloads.join("video (1).mp4"), downloads.join("video.mp4")].map(|p| p.to_string_lossy().into_owned())
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn duplicates_are_detected_by_video_id() {
+    let dir = test_dir("duplicates");
+    let (core, recorder) = start(&dir, &dir.join("yt-dlp").to_string_lossy(), 3);
+
+    core.queue_download("https://example.test/videos/abc", None);
+    wait_until(|| finished(&core, 1)).await;
+
+    // Mesma URL com rastreamento, fragmento e host móvel
+    assert_eq!(
+        core.queue_download("https://m.example.test/videos/abc/?utm_source=feed#t=30", None),
+        QueueOutcome::AlreadyDownloaded
+    );
+
+    // URL diferente do mesmo vídeo: o yt-dlp resolve o id antes de baixar
+    assert_eq!(core.queue_download("https://mirror.test/watch/abc", None), QueueOutcome::Queued);
+    wait_until(|| {
+        recorder.notifications.lock().unwrap().contains(&"Vídeo Já Baixado".to_string())
+            && core.queue.active_count() == 0
+    })
+    .await;
+    assert_eq!(core.history.entries().len(), 1);
+
+    // Variantes do mesmo vídeo do YouTube não entram duas vezes na fila
+    core.queue.begin_shutdown(QuitMode::Pause);
+    assert_eq!(core.queue_download("https://youtu.be/dQw4w9WgXcQ?si=x", None), QueueOutcome::Queued);
+    assert_eq!(
+        core.queue_download("https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=30", None),
+        QueueOutcome::AlreadyQueued
+    );
+    assert_eq!(
+        core.queue_download("https://youtube.com/shorts/dQw4w9WgXcQ", None),
+        QueueOutcome::AlreadyQueued
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn requeue_on_quit_keeps_downloads_in_the_queue() {
+    let dir = test_dir("requeue-quit");
+    let (core, _) = start(&dir, &dir.join("yt-dlp").to_string_lossy(), 1);
+
+    core.queue_download("https://example.test/slow-quit", None);
+    wait_until(|| core.queue.active_count() == 1).await;
+    core.queue.begin_shutdown(QuitMode::Requeue);
+    wait_until(|| core.queue.active_count() == 0).await;
+
+    let status = core.queue.get_status();
+    assert_eq!((status.queued, status.paused, status.failed), (1, 0, 0));
+    assert_eq!(status.downloads[0].url, "https://example.test/slow-quit");
+    assert!(core.history.entries().is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn bulk_queue_notifies_once() {
+    let dir = test_dir("bulk");
+    let (core, recorder) = start(&dir, &dir.join("yt-dlp").to_string_lossy(), 3);
+
+    core.queue_download("https://example.test/first", None);
+    wait_until(|| finished(&core, 1)).await;
+    recorder.notifications.lock().unwrap().clear();
+
+    let urls: Vec<String> = ["first", "second", "third", "second"]
+        .iter()
+        .map(|id| format!("https://example.test/{}", id))
+        .collect();
+    assert_eq!(
+        core.queue_downloads(&urls, None),
+        vec![
+            QueueOutcome::AlreadyDownloaded,
+            QueueOutcome::Queued,
+            QueueOutcome::Queued,
+            QueueOutcome::AlreadyQueued,
+        ]
+    );
+    wait_until(|| finished(&core, 3)).await;
+
+    let notifications = recorder.notifications.lock().unwrap();
+    assert_eq!(notifications.iter().filter(|title| *title == "Adicionado à Fila").count(), 1);
+    assert!(!notifications.contains(&"Vídeo Já Baixado".to_string()));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn playlist_listing_stops_at_the_newest_items() {
+    let dir = test_dir("playlist");
+    let (core, recorder) = start(&dir, &dir.join("yt-dlp").to_string_lossy(), 3);
+
+    let mut settings = core.settings.get().as_ref().clone();
+    settings.playlist.expand = true;
+    settings.playlist.newest = Some(2);
+    core.update_settings(settings);
+
+    assert_eq!(
+        core.queue_download("https://example.test/playlist?list=abc", None),
+        QueueOutcome::Expanding
+    );
+    wait_until(|| finished(&core, 2)).await;
+
+    assert_eq!(fs::read_to_string(dir.join("playlist-end")).unwrap().trim(), "2");
+    let mut urls: Vec<String> = core.history.entries().into_iter().map(|entry| entry.url).collect();
+    urls.sort();
+    assert_eq!(urls, ["https://example.test/item1", "https://example.test/item2"]);
+    assert!(recorder.notifications.lock().unwrap().contains(&"Playlist Adicionada".to_string()));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn output_folder_comes_from_request_or_site_rule() {
+    let dir = test_dir("output");
+    let (core, _recorder) = start(&dir, &dir.join("yt-dlp").to_string_lossy(), 3);
+
+    let mut settings = core.settings.get().as_ref().clone();
+    let mut rule = SiteRule::allow("example.test");
+    rule.output.output_dir = Some(dir.join("rule").to_string_lossy().into_owned());
+    settings.site_rules.insert(0, rule);
+    core.update_settings(settings);
+
+    core.queue_download("https://example.test/from-rule", None);
+    let output = OutputOptions {
+        output_dir: Some(dir.join("request").to_string_lossy().into_owned()),
+        ..OutputOptions::default()
+    };
+    core.queue_download_with("https://example.test/from-request", None, output).unwrap();
+    let outside = OutputOptions {
+        filename_template: Some("../%(id)s.%(ext)s".to_string()),
+        ..OutputOptions::default()
+    };
+    assert!(core.queue_download_with("https://example.test/outside", None, outside).is_err());
+    core.queue_download("https://other.test/from-settings", None);
+    wait_until(|| finished(&core, 3)).await;
+
+    assert!(dir.join("rule").join("from-rule.mp4").is_file());
+    assert!(dir.join("request").join("from-request.mp4").is_file());
+    assert!(dir.join("downloads").join("from-settings.mp4").is_file());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn settings_are_validated_before_saving() {
+    let dir = test_dir("validation");
+    let (core, _recorder) = start(&dir, &dir.join("yt-dlp").to_string_lossy(), 3);
+
+    let mut settings = core.settings.get().as_ref().clone();
+    settings.max_concurrent = 0;
+    settings.filename_template = "%(invalid)s.%(ext)s".to_string();
+    let errors = core.save_settings(settings).await.unwrap_err();
+    let fields: Vec<&str> = errors.iter().map(|error| error.field.as_str()).collect();
+    assert_eq!(fields, ["max_concurrent", "filename_template"]);
+    assert_eq!(errors[1].message, "Modelo inválido: invalid output template");
+    assert_eq!(core.settings.get().max_concurrent, 3);
+
+    let mut settings = core.settings.get().as_ref().clone();
+    settings.download_path = dir.join("nova").join("pasta").to_string_lossy().into_owned();
+    settings.filename_template = "%(title)s.%(ext)s".to_string();
+    core.save_settings(settings).await.unwrap();
+    assert!(dir.join("nova").join("pasta").is_dir());
+    assert_eq!(core.settings.get().filename_template, "%(title)s.%(ext)s");
+
+    assert!(core.set_config_value("max_concurrent", 0.into()).is_err());
+    assert_eq!(core.settings.get().max_concurrent, 3);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn config_api_cannot_change_executables() {
+    let dir = test_dir("executables");
+    let (core, _recorder) = start(&dir, &dir.join("yt-dlp").to_string_lossy(), 3);
+
+    assert!(core.set_config_value("ytdlp_path", "/bin/sh".into()).is_err());
+    assert!(core.set_config_value("backends.gallery_dl_path", "/bin/sh".into()).is_err());
+    let mut backends = core.config_value(Some("backends")).unwrap();
+    backends["youtube_dl_path"] = "/bin/sh".into();
+    assert!(core.set_config_value("backends", backends).is_err());
+
+    assert_eq!(core.set_config_value("max_concurrent", 2.into()), Ok(()));
+    assert_eq!(core.settings.get().max_concurrent, 2);
+    assert_eq!(core.settings.get().ytdlp_path, dir.join("yt-dlp").to_string_lossy());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn only_http_urls_are_queued() {
+    let dir = test_dir("invalid-url");
+    let (core, _recorder) = start(&dir, &dir.join("yt-dlp").to_string_lossy(), 3);
+
+    for url in ["--exec=touch /tmp/x", "file:///etc/passwd", "-o/tmp/x https://a.test/v"] {
+        assert_eq!(core.queue_download(url, None), QueueOutcome::InvalidUrl, "{}", url);
+    }
+    assert_eq!(core.queue.get_status().queued, 0);
+}
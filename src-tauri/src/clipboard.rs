@@ -0,0 +1,135 @@
+// Leitura da área de transferência em cada plataforma
+
+#[cfg(target_os = "windows")]
+use clipboard_win::{formats, get_clipboard};
+
+#[cfg(all(unix, not(target_os = "macos")))]
+use arboard::{GetExtLinux, LinuxClipboardKind};
+
+// Seleções que podem ser monitoradas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Selection {
+    // Área de transferência normal (Ctrl+C)
+    Clipboard,
+    // Seleção PRIMARY do X11/Wayland (texto selecionado com o mouse)
+    Primary,
+}
+
+// Origem de texto consultada pelo loop de monitoramento
+pub trait ClipboardSource {
+    fn read_text(&mut self) -> Option<String>;
+}
+
+#[cfg(target_os = "windows")]
+struct WindowsClipboard;
+
+#[cfg(target_os = "windows")]
+impl ClipboardSource for WindowsClipboard {
+    fn read_text(&mut self) -> Option<String> {
+        get_clipboard::<String, _>(formats::Unicode).ok()
+    }
+}
+
+// X11, Wayland (protocolo data-control) e macOS via arboard
+#[cfg(not(target_os = "windows"))]
+struct ArboardClipboard {
+    clipboard: Option<arboard::Clipboard>,
+    #[cfg_attr(target_os = "macos", allow(dead_code))]
+    selection: Selection,
+}
+
+#[cfg(not(target_os = "windows"))]
+impl ArboardClipboard {
+    fn new(selection: Selection) -> Self {
+        Self {
+            clipboard: None,
+            selection,
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+impl ClipboardSource for ArboardClipboard {
+    fn read_text(&mut self) -> Option<String> {
+        // Conecta sob demanda: o servidor gráfico pode não estar pronto no início da sessão
+        if self.clipboard.is_none() {
+            self.clipboard = arboard::Clipboard::new().ok();
+        }
+        let clipboard = self.clipboard.as_mut()?;
+
+        #[cfg(all(unix, not(target_os = "macos")))]
+        let result = {
+            let kind = match self.selection {
+                Selection::Clipboard => LinuxClipboardKind::Clipboard,
+                Selection::Primary => LinuxClipboardKind::Primary,
+            };
+            clipboard.get().clipboard(kind).text()
+        };
+
+        #[cfg(target_os = "macos")]
+        let result = clipboard.get_text();
+
+        result.ok()
+    }
+}
+
+fn create_source(selection: Selection) -> Option<Box<dyn ClipboardSource>> {
+    #[cfg(target_os = "windows")]
+    {
+        match selection {
+            Selection::Clipboard => Some(Box::new(WindowsClipboard)),
+            Selection::Primary => None,
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        match selection {
+            Selection::Clipboard => Some(Box::new(ArboardClipboard::new(selection))),
+            Selection::Primary => None,
+        }
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        Some(Box::new(ArboardClipboard::new(selection)))
+    }
+}
+
+// Acompanha as seleções escolhidas e devolve apenas textos novos
+#[derive(Default)]
+pub struct ClipboardWatcher {
+    selections: Vec<Selection>,
+    sources: Vec<(Box<dyn ClipboardSource>, String)>,
+}
+
+impl ClipboardWatcher {
+    // Recria as fontes apenas quando as seleções configuradas mudam
+    pub fn set_selections(&mut self, selections: Vec<Selection>) {
+        if selections == self.selections {
+            return;
+        }
+
+        self.sources = selections
+            .iter()
+            .filter_map(|selection| create_source(*selection))
+            .map(|source| (source, String::new()))
+            .collect();
+        self.selections = selections;
+    }
+
+    pub fn poll(&mut self) -> Vec<String> {
+        let mut changed = Vec::new();
+
+        for (source, previous) in self.sources.iter_mut() {
+            if let Some(text) = source.read_text() {
+                if text != *previous {
+                    *previous = text.clone();
+                    changed.push(text);
+                }
+            }
+        }
+
+        changed
+    }
+}
@@ -1,437 +1,287 @@
 #![windows_subsystem = "windows"]
 
-use std::sync::{Arc, Mutex};
+mod clipboard;
+
+use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
-use std::process::Stdio;
-use std::collections::{VecDeque, HashSet};
-use std::fs;
-use std::path::PathBuf;
-use serde::{Deserialize, Serialize};
+use chrono::Local;
+use serde::Serialize;
 use tauri::{
-    AppHandle, Manager,
-    menu::{Menu, MenuItem},
+    AppHandle, Emitter, Manager,
+    menu::{Menu, MenuItem, PredefinedMenuItem, Submenu},
     tray::{TrayIconBuilder, MouseButton, MouseButtonState},
 };
 use tauri_plugin_notification::NotificationExt;
-use chrono::{DateTime, Utc};
 
-#[cfg(target_os = "windows")]
-use clipboard_win::{formats, get_clipboard};
+use clipboard::{ClipboardWatcher, Selection};
+use ytdl_monitor_core::api::ApiServer;
+use ytdl_monitor_core::auto_download::PendingDownloads;
+use ytdl_monitor_core::backends;
+use ytdl_monitor_core::extractors::{self, Detection, ExtractorCache};
+use ytdl_monitor_core::output::OutputOptions;
+use ytdl_monitor_core::queue::{DownloadProgress, Priority, QuitMode};
+use ytdl_monitor_core::recent_urls::{self, RecentUrl, RecentUrls};
+use ytdl_monitor_core::settings;
+use ytdl_monitor_core::validation::{self, FieldError};
+use ytdl_monitor_core::{cli, ipc, rules, Core, DownloadStatus, EventSink, HistoryEntry, QueueOutcome, Settings};
 
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
 
-// Estruturas de configuração
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct Settings {
-    download_path: String,
-    filename_template: String,
-    max_concurrent: usize,
-    enable_notifications: bool,
-    enable_sound: bool,
-    check_duplicates: bool,
-}
-
-impl Default for Settings {
-    fn default() -> Self {
-        Self {
-            download_path: "C:\\Users\\Lucas\\Desktop".to_string(),
-            filename_template: "%(title)s.%(ext)s".to_string(),
-            max_concurrent: 3,
-            enable_notifications: true,
-            enable_sound: true,
-            check_duplicates: true,
-        }
-    }
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct DownloadHistory {
-    urls: HashSet<String>,
-    last_updated: DateTime<Utc>,
-}
-
-impl DownloadHistory {
-    fn new() -> Self {
-        Self {
-            urls: HashSet::new(),
-            last_updated: Utc::now(),
-        }
+// Seleções monitoradas; PRIMARY só existe no X11/Wayland
+fn clipboard_selections(settings: &Settings) -> Vec<Selection> {
+    let mut selections = Vec::new();
+    if settings.watch_clipboard {
+        selections.push(Selection::Clipboard);
     }
-
-    fn add(&mut self, url: String) {
-        self.urls.insert(url);
-        self.last_updated = Utc::now();
+    if settings.watch_primary_selection {
+        selections.push(Selection::Primary);
     }
+    selections
+}
 
-    fn contains(&self, url: &str) -> bool {
-        self.urls.contains(url)
+// Função para tocar som de conclusão
+fn play_completion_sound() {
+    #[cfg(target_os = "windows")]
+    {
+        use std::process::Command;
+        let _ = Command::new("powershell")
+            .args(&[
+                "-c",
+                "(New-Object Media.SoundPlayer 'C:\\Windows\\Media\\Windows Notify System Generic.wav').PlaySync()"
+            ])
+            .creation_flags(0x08000000)
+            .output();
     }
 }
 
 #[derive(Debug, Clone, Serialize)]
-struct DownloadItem {
+struct DownloadProgressEvent {
     url: String,
-    status: String,
-}
-
-#[derive(Debug, Clone, Serialize)]
-struct DownloadStatus {
-    active: usize,
-    queued: usize,
-    downloads: Vec<DownloadItem>,
+    progress: DownloadProgress,
 }
 
-// Gerenciador de configurações
-struct SettingsManager {
-    settings: Arc<Mutex<Settings>>,
-    config_path: PathBuf,
+// Eventos do núcleo viram notificações do sistema e eventos para as janelas abertas
+struct TraySink {
+    app: AppHandle,
 }
 
-impl SettingsManager {
-    fn new() -> Self {
-        let config_dir = dirs::config_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join("ytdl-monitor");
-        
-        fs::create_dir_all(&config_dir).ok();
-        
-        let config_path = config_dir.join("settings.json");
-        let settings = Self::load_from_file(&config_path);
-        
-        Self {
-            settings: Arc::new(Mutex::new(settings)),
-            config_path,
-        }
+impl EventSink for TraySink {
+    fn notify(&self, title: &str, body: &str) {
+        let _ = self.app.notification()
+            .builder()
+            .title(title)
+            .body(body)
+            .show();
     }
 
-    fn load_from_file(path: &PathBuf) -> Settings {
-        fs::read_to_string(path)
-            .ok()
-            .and_then(|s| serde_json::from_str(&s).ok())
-            .unwrap_or_default()
+    fn status_changed(&self, status: &DownloadStatus) {
+        let _ = self.app.emit("download-status", status);
     }
 
-    fn save(&self) {
-        let settings = self.settings.lock().unwrap().clone();
-        if let Ok(json) = serde_json::to_string_pretty(&settings) {
-            fs::write(&self.config_path, json).ok();
-        }
+    fn progress(&self, url: &str, progress: &DownloadProgress) {
+        let _ = self.app.emit("download-progress", DownloadProgressEvent {
+            url: url.to_string(),
+            progress: progress.clone(),
+        });
     }
 
-    fn get(&self) -> Settings {
-        self.settings.lock().unwrap().clone()
+    fn completion_sound(&self) {
+        tauri::async_runtime::spawn_blocking(play_completion_sound);
     }
 
-    fn update(&self, new_settings: Settings) {
-        *self.settings.lock().unwrap() = new_settings;
-        self.save();
+    fn settings_changed(&self, core: &Arc<Core>, settings: &Settings) {
+        self.app.state::<ApiServer>().apply(core, &settings.api);
+        refresh_tray_menu(&self.app);
     }
 }
 
-// Gerenciador de histórico
-struct HistoryManager {
-    history: Arc<Mutex<DownloadHistory>>,
-    history_path: PathBuf,
+// Comandos Tauri
+#[tauri::command]
+fn get_settings(core: tauri::State<Arc<Core>>) -> Settings {
+    core.settings.get().as_ref().clone()
 }
 
-impl HistoryManager {
-    fn new() -> Self {
-        let config_dir = dirs::config_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join("ytdl-monitor");
-        
-        let history_path = config_dir.join("history.json");
-        let history = Self::load_from_file(&history_path);
-        
-        Self {
-            history: Arc::new(Mutex::new(history)),
-            history_path,
-        }
-    }
-
-    fn load_from_file(path: &PathBuf) -> DownloadHistory {
-        fs::read_to_string(path)
-            .ok()
-            .and_then(|s| serde_json::from_str(&s).ok())
-            .unwrap_or_else(DownloadHistory::new)
-    }
+// Erros por campo aparecem ao lado de cada campo na janela de configurações
+#[tauri::command]
+async fn save_settings(settings: Settings, core: tauri::State<'_, Arc<Core>>) -> Result<(), Vec<FieldError>> {
+    core.save_settings(settings).await
+}
 
-    fn save(&self) {
-        let history = self.history.lock().unwrap();
-        if let Ok(json) = serde_json::to_string_pretty(&*history) {
-            fs::write(&self.history_path, json).ok();
-        }
-    }
+// Pasta Downloads do sistema para o botão da janela de configurações
+#[tauri::command]
+fn default_download_dir(subfolder: bool) -> String {
+    settings::default_download_dir(subfolder).to_string_lossy().into_owned()
+}
 
-    fn add(&self, url: String) {
-        self.history.lock().unwrap().add(url);
-        self.save();
-    }
+// Exemplo do nome gerado pelo modelo; None sem o yt-dlp instalado
+#[tauri::command]
+async fn preview_filename_template(
+    template: String,
+    core: tauri::State<'_, Arc<Core>>,
+) -> Result<Option<String>, String> {
+    let ytdlp = core.settings.get().ytdlp_path.clone();
+    validation::preview_filename(&ytdlp, template.trim()).await
+}
 
-    fn contains(&self, url: &str) -> bool {
-        self.history.lock().unwrap().contains(url)
-    }
+// Pasta, modelo, legendas e pós-processamento enviados pela janela valem só para este download
+#[tauri::command]
+fn add_download(
+    url: String,
+    profile: Option<String>,
+    output: Option<OutputOptions>,
+    core: tauri::State<Arc<Core>>,
+) -> Result<QueueOutcome, Vec<FieldError>> {
+    core.queue_download_with(url.trim(), profile, output.unwrap_or_default())
 }
 
-// Fila de downloads
-struct DownloadQueue {
-    queue: Arc<Mutex<VecDeque<String>>>,
-    active: Arc<Mutex<Vec<String>>>,
+#[tauri::command]
+fn get_download_status(core: tauri::State<Arc<Core>>) -> DownloadStatus {
+    core.queue.get_status()
 }
 
-impl DownloadQueue {
-    fn new() -> Self {
-        Self {
-            queue: Arc::new(Mutex::new(VecDeque::new())),
-            active: Arc::new(Mutex::new(Vec::new())),
-        }
+#[tauri::command]
+fn cancel_download(url: String, core: tauri::State<Arc<Core>>) -> bool {
+    let changed = core.queue.cancel(&url);
+    if changed {
+        core.emit_status();
     }
+    changed
+}
 
-    fn add(&self, url: String) -> bool {
-        let mut queue = self.queue.lock().unwrap();
-        let active = self.active.lock().unwrap();
-        
-        if queue.contains(&url) || active.contains(&url) {
-            return false;
-        }
-        
-        queue.push_back(url);
-        true
+#[tauri::command]
+fn pause_download(url: String, core: tauri::State<Arc<Core>>) -> bool {
+    let changed = core.queue.pause(&url);
+    if changed {
+        core.emit_status();
     }
+    changed
+}
 
-    fn get_next(&self) -> Option<String> {
-        self.queue.lock().unwrap().pop_front()
+#[tauri::command]
+fn resume_download(url: String, core: tauri::State<Arc<Core>>) -> bool {
+    let changed = core.queue.resume(&url);
+    if changed {
+        core.emit_status();
     }
+    changed
+}
 
-    fn can_start(&self, max_concurrent: usize) -> bool {
-        self.active.lock().unwrap().len() < max_concurrent
+#[tauri::command]
+fn set_download_priority(
+    url: String,
+    priority: Priority,
+    core: tauri::State<Arc<Core>>,
+) -> bool {
+    let changed = core.queue.set_priority(&url, priority);
+    if changed {
+        core.emit_status();
     }
+    changed
+}
 
-    fn mark_active(&self, url: String) {
-        self.active.lock().unwrap().push(url);
+#[tauri::command]
+fn move_download_up(url: String, core: tauri::State<Arc<Core>>) -> bool {
+    let changed = core.queue.move_up(&url);
+    if changed {
+        core.emit_status();
     }
+    changed
+}
 
-    fn remove_active(&self, url: &str) {
-        self.active.lock().unwrap().retain(|u| u != url);
+#[tauri::command]
+fn move_download_down(url: String, core: tauri::State<Arc<Core>>) -> bool {
+    let changed = core.queue.move_down(&url);
+    if changed {
+        core.emit_status();
     }
+    changed
+}
 
-    fn get_status(&self) -> DownloadStatus {
-        let active = self.active.lock().unwrap();
-        let queue = self.queue.lock().unwrap();
-        
-        let mut downloads = Vec::new();
-        
-        for url in active.iter() {
-            downloads.push(DownloadItem {
-                url: url.clone(),
-                status: "active".to_string(),
-            });
-        }
-        
-        for url in queue.iter() {
-            downloads.push(DownloadItem {
-                url: url.clone(),
-                status: "queued".to_string(),
-            });
-        }
-        
-        DownloadStatus {
-            active: active.len(),
-            queued: queue.len(),
-            downloads,
-        }
+#[tauri::command]
+fn download_next(url: String, core: tauri::State<Arc<Core>>) -> bool {
+    let changed = core.queue.download_next(&url);
+    if changed {
+        core.emit_status();
     }
+    changed
 }
 
-// Função para obter o conteúdo da área de transferência
-#[cfg(target_os = "windows")]
-fn get_clipboard_text() -> Option<String> {
-    match get_clipboard::<String, _>(formats::Unicode) {
-        Ok(text) => Some(text),
-        Err(_) => None,
+#[tauri::command]
+fn pin_download(url: String, core: tauri::State<Arc<Core>>) -> bool {
+    let changed = core.queue.pin(&url);
+    if changed {
+        core.emit_status();
     }
+    changed
 }
 
-// Função para verificar se é uma URL de vídeo válida
-fn is_video_url(url: &str) -> bool {
-    let video_domains = [
-        "pornhub.com",
-        "xvideos.com",
-        "xhamster.com",
-        "youtube.com",
-        "youtu.be",
-        "vimeo.com",
-        "dailymotion.com",
-        "twitch.tv",
-        "tiktok.com",
-        "instagram.com",
-        "facebook.com",
-        "twitter.com",
-        "x.com",
-    ];
+#[tauri::command]
+fn retry_download(url: String, core: tauri::State<Arc<Core>>) -> bool {
+    let changed = core.queue.retry_failed(&url);
+    if changed {
+        core.emit_status();
+    }
+    changed
+}
 
-    let url_lower = url.to_lowercase();
-    
-    if !url_lower.starts_with("http://") && !url_lower.starts_with("https://") {
-        return false;
+#[tauri::command]
+fn dismiss_failed_download(url: String, core: tauri::State<Arc<Core>>) -> bool {
+    let changed = core.queue.dismiss_failed(&url);
+    if changed {
+        core.emit_status();
     }
+    changed
+}
 
-    video_domains.iter().any(|domain| url_lower.contains(domain))
+#[tauri::command]
+fn get_history(core: tauri::State<Arc<Core>>) -> Vec<HistoryEntry> {
+    core.history.entries()
 }
 
-// Função para tocar som de conclusão
-fn play_completion_sound() {
-    #[cfg(target_os = "windows")]
-    {
-        use std::process::Command;
-        let _ = Command::new("powershell")
-            .args(&[
-                "-c",
-                "(New-Object Media.SoundPlayer 'C:\\Windows\\Media\\Windows Notify System Generic.wav').PlaySync()"
-            ])
-            .creation_flags(0x08000000)
-            .output();
-    }
+#[tauri::command]
+fn search_history(query: String, core: tauri::State<Arc<Core>>) -> Vec<HistoryEntry> {
+    core.history.search(&query)
 }
 
-// Função para baixar o vídeo usando yt-dlp
-fn download_video(
-    url: &str,
-    app_handle: &AppHandle,
-    settings: &Settings,
-    history: Arc<HistoryManager>,
-) -> bool {
-    #[cfg(target_os = "windows")]
-    let output = std::process::Command::new("yt-dlp")
-        .arg("-f")
-        .arg("best")
-        .arg("-o")
-        .arg(format!("{}\\{}", settings.download_path, settings.filename_template))
-        .arg(url)
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .creation_flags(0x08000000)
-        .output();
-
-    #[cfg(not(target_os = "windows"))]
-    let output = std::process::Command::new("yt-dlp")
-        .arg("-f")
-        .arg("best")
-        .arg("-o")
-        .arg(format!("{}/{}", settings.download_path, settings.filename_template))
-        .arg(url)
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .output();
-
-    match output {
-        Ok(result) => {
-            if result.status.success() {
-                history.add(url.to_string());
-                
-                if settings.enable_notifications {
-                    let _ = app_handle.notification()
-                        .builder()
-                        .title("Download Concluído")
-                        .body("Vídeo baixado com sucesso!")
-                        .show();
-                }
-                
-                if settings.enable_sound {
-                    play_completion_sound();
-                }
-                
-                true
-            } else {
-                if settings.enable_notifications {
-                    let _ = app_handle.notification()
-                        .builder()
-                        .title("Erro no Download")
-                        .body("Não foi possível baixar o vídeo")
-                        .show();
-                }
-                false
-            }
-        }
-        Err(_e) => {
-            if settings.enable_notifications {
-                let _ = app_handle.notification()
-                    .builder()
-                    .title("Erro")
-                    .body("yt-dlp não encontrado. Verifique a instalação.")
-                    .show();
-            }
-            false
-        }
-    }
+#[tauri::command]
+fn delete_history_entry(id: u64, core: tauri::State<Arc<Core>>) -> bool {
+    core.history.delete(id)
 }
 
-// Processador de fila de downloads
-fn process_download_queue(
-    queue: Arc<DownloadQueue>,
-    app_handle: AppHandle,
-    settings_manager: Arc<SettingsManager>,
-    history_manager: Arc<HistoryManager>,
-) {
-    thread::spawn(move || {
-        loop {
-            thread::sleep(Duration::from_millis(500));
+#[tauri::command]
+fn get_download_log(url: String, core: tauri::State<Arc<Core>>) -> Option<String> {
+    core.download_log(&url)
+}
 
-            let settings = settings_manager.get();
-            
-            if queue.can_start(settings.max_concurrent) {
-                if let Some(url) = queue.get_next() {
-                    let queue_clone = queue.clone();
-                    let app_clone = app_handle.clone();
-                    let settings_clone = settings.clone();
-                    let history_clone = history_manager.clone();
-                    let url_clone = url.clone();
-                    
-                    queue.mark_active(url.clone());
-                    
-                    if settings.enable_notifications {
-                        let status = queue.get_status();
-                        let _ = app_handle.notification()
-                            .builder()
-                            .title("Download Iniciado")
-                            .body(format!(
-                                "{} ativo{}, {} na fila",
-                                status.active,
-                                if status.active > 1 { "s" } else { "" },
-                                status.queued
-                            ))
-                            .show();
-                    }
-                    
-                    thread::spawn(move || {
-                        download_video(&url_clone, &app_clone, &settings_clone, history_clone);
-                        queue_clone.remove_active(&url_clone);
-                    });
-                }
-            }
-        }
-    });
+#[tauri::command]
+fn get_recent_urls(recent: tauri::State<Arc<RecentUrls>>) -> Vec<RecentUrl> {
+    recent.list()
 }
 
-// Comandos Tauri
 #[tauri::command]
-fn get_settings(settings_manager: tauri::State<Arc<SettingsManager>>) -> Settings {
-    settings_manager.get()
+fn queue_recent_urls(
+    urls: Vec<String>,
+    profile: Option<String>,
+    core: tauri::State<Arc<Core>>,
+) -> Vec<QueueOutcome> {
+    core.queue_downloads(&urls, profile)
 }
 
 #[tauri::command]
-fn save_settings(
-    settings: Settings,
-    settings_manager: tauri::State<Arc<SettingsManager>>,
-) {
-    settings_manager.update(settings);
+fn remove_recent_url(url: String, app: AppHandle) -> bool {
+    let removed = app.state::<Arc<RecentUrls>>().remove(&url);
+    if removed {
+        recent_urls_changed(&app);
+    }
+    removed
 }
 
 #[tauri::command]
-fn get_download_status(queue: tauri::State<Arc<DownloadQueue>>) -> DownloadStatus {
-    queue.get_status()
+fn clear_recent_urls(app: AppHandle) {
+    app.state::<Arc<RecentUrls>>().clear();
+    recent_urls_changed(&app);
 }
 
 // Comandos para abrir janelas
@@ -489,158 +339,505 @@ fn open_progress_window(app: AppHandle) {
     .build();
 }
 
+#[tauri::command]
+fn open_history_window(app: AppHandle) {
+    let window_label = "history";
+    
+    // Se janela já existe, apenas mostra
+    if let Some(window) = app.get_webview_window(window_label) {
+        let _ = window.show();
+        let _ = window.set_focus();
+        return;
+    }
+    
+    // Criar nova janela
+    use tauri::WebviewWindowBuilder;
+    use tauri::WebviewUrl;
+    
+    let _window = WebviewWindowBuilder::new(
+        &app,
+        window_label,
+        WebviewUrl::App("history.html".into())
+    )
+    .title("Histórico - YT-DLP Monitor")
+    .inner_size(650.0, 550.0)
+    .resizable(true)
+    .center()
+    .build();
+}
+
+#[tauri::command]
+fn open_recent_window(app: AppHandle) {
+    let window_label = "recent";
+    
+    // Se janela já existe, apenas mostra
+    if let Some(window) = app.get_webview_window(window_label) {
+        let _ = window.show();
+        let _ = window.set_focus();
+        return;
+    }
+    
+    // Criar nova janela
+    use tauri::WebviewWindowBuilder;
+    use tauri::WebviewUrl;
+    
+    let _window = WebviewWindowBuilder::new(
+        &app,
+        window_label,
+        WebviewUrl::App("recent.html".into())
+    )
+    .title("URLs Recentes - YT-DLP Monitor")
+    .inner_size(600.0, 500.0)
+    .resizable(true)
+    .center()
+    .build();
+}
+
+#[tauri::command]
+fn open_quit_window(app: AppHandle) {
+    let window_label = "quit";
+    
+    // Se janela já existe, apenas mostra
+    if let Some(window) = app.get_webview_window(window_label) {
+        let _ = window.show();
+        let _ = window.set_focus();
+        return;
+    }
+    
+    // Criar nova janela
+    use tauri::WebviewWindowBuilder;
+    use tauri::WebviewUrl;
+    
+    let _window = WebviewWindowBuilder::new(
+        &app,
+        window_label,
+        WebviewUrl::App("quit.html".into())
+    )
+    .title("Sair - YT-DLP Monitor")
+    .inner_size(420.0, 320.0)
+    .resizable(false)
+    .center()
+    .build();
+}
+
+// Encerra o aplicativo depois de tratar os downloads ativos; a fila restante já está salva
+#[tauri::command]
+fn quit_app(mode: QuitMode, app: AppHandle, core: tauri::State<Arc<Core>>) {
+    core.queue.begin_shutdown(mode);
+    core.emit_status();
+
+    if let Some(window) = app.get_webview_window("quit") {
+        let _ = window.close();
+    }
+
+    let core = core.inner().clone();
+    thread::spawn(move || {
+        while core.queue.active_count() > 0 {
+            thread::sleep(Duration::from_millis(200));
+        }
+        ipc::remove_socket();
+        app.exit(0);
+    });
+}
+
+const TRAY_ID: &str = "main";
+const PROFILE_MENU_PREFIX: &str = "profile:";
+const RECENT_MENU_PREFIX: &str = "recent:";
+
+// Horário da detecção e a URL encurtada para caber no menu
+fn recent_menu_label(entry: &RecentUrl) -> String {
+    const MAX_CHARS: usize = 60;
+    let url = entry.url.trim_start_matches("https://").trim_start_matches("http://");
+    let url = if url.chars().count() > MAX_CHARS {
+        format!("{}…", url.chars().take(MAX_CHARS - 1).collect::<String>())
+    } else {
+        url.to_string()
+    };
+    format!("{}  {}", entry.detected_at.with_timezone(&Local).format("%H:%M"), url)
+}
+
+// Menu do tray; os submenus de perfis e de URLs recentes acompanham as configurações e as cópias
+fn build_tray_menu(app: &AppHandle, settings: &Settings, recent: &[RecentUrl]) -> tauri::Result<Menu<tauri::Wry>> {
+    let recent_menu = Submenu::new(app, "📋 URLs Recentes", true)?;
+    if recent.is_empty() {
+        let empty_item = MenuItem::with_id(app, "recent_empty", "Nenhuma URL copiada", false, None::<&str>)?;
+        recent_menu.append(&empty_item)?;
+    } else {
+        for entry in recent {
+            let item = MenuItem::with_id(
+                app,
+                format!("{}{}", RECENT_MENU_PREFIX, entry.url),
+                recent_menu_label(entry),
+                true,
+                None::<&str>,
+            )?;
+            recent_menu.append(&item)?;
+        }
+        recent_menu.append(&PredefinedMenuItem::separator(app)?)?;
+        recent_menu.append(&MenuItem::with_id(app, "recent_all", "⬇️ Baixar Todas", true, None::<&str>)?)?;
+        recent_menu.append(&MenuItem::with_id(app, "recent_pick", "🗂️ Escolher...", true, None::<&str>)?)?;
+        recent_menu.append(&MenuItem::with_id(app, "recent_clear", "🧹 Limpar", true, None::<&str>)?)?;
+    }
+
+    let profiles_menu = Submenu::new(app, "🎞️ Baixar Como...", true)?;
+    for profile in settings.format_profiles.iter() {
+        let item = MenuItem::with_id(
+            app,
+            format!("{}{}", PROFILE_MENU_PREFIX, profile.id),
+            &profile.name,
+            true,
+            None::<&str>,
+        )?;
+        profiles_menu.append(&item)?;
+    }
+
+    let progress_item = MenuItem::with_id(app, "progress", "📊 Ver Progresso", true, None::<&str>)?;
+    let history_item = MenuItem::with_id(app, "history", "📜 Histórico", true, None::<&str>)?;
+    let settings_item = MenuItem::with_id(app, "settings", "⚙️ Configurações", true, None::<&str>)?;
+    let undo_item = MenuItem::with_id(app, "undo_auto", "↩️ Desfazer Download Automático", true, None::<&str>)?;
+    let quit_item = MenuItem::with_id(app, "quit", "❌ Sair", true, None::<&str>)?;
+
+    Menu::with_items(app, &[
+        &recent_menu,
+        &profiles_menu,
+        &undo_item,
+        &progress_item,
+        &history_item,
+        &settings_item,
+        &quit_item,
+    ])
+}
+
+fn refresh_tray_menu(app: &AppHandle) {
+    let settings = app.state::<Arc<Core>>().settings.get();
+    let recent = app.state::<Arc<RecentUrls>>().list();
+    if let (Some(tray), Ok(menu)) = (app.tray_by_id(TRAY_ID), build_tray_menu(app, &settings, &recent)) {
+        let _ = tray.set_menu(Some(menu));
+    }
+}
+
+// Atualiza o submenu e a janela de URLs recentes
+fn recent_urls_changed(app: &AppHandle) {
+    let _ = app.emit("recent-urls", app.state::<Arc<RecentUrls>>().list());
+    refresh_tray_menu(app);
+}
+
+// A URL mais recente, ou vazia para o aviso de "Sem URL"
+fn latest_url(app: &AppHandle) -> String {
+    app.state::<Arc<RecentUrls>>()
+        .latest()
+        .map(|entry| entry.url)
+        .unwrap_or_default()
+}
+
+// Guarda a URL detectada no histórico e mostra no tooltip como ela foi reconhecida
+fn accept_detected_url(app_handle: &AppHandle, url: &str, source: &str) {
+    let capacity = app_handle.state::<Arc<Core>>().settings.get().clipboard_history_size;
+    app_handle.state::<Arc<RecentUrls>>().push(url, source, capacity);
+    recent_urls_changed(app_handle);
+    
+    if let Some(tray) = app_handle.tray_by_id(TRAY_ID) {
+        let _ = tray.set_tooltip(Some(format!(
+            "YT-DLP Monitor - Clique para baixar\n{}: {}",
+            source, url
+        )));
+    }
+    
+    schedule_auto_download(app_handle, url);
+}
+
+// Verifica o texto copiado pelas regras de sites e, se habilitado, pelo próprio yt-dlp
+fn handle_clipboard_url(app_handle: &AppHandle, url: String, settings: &Settings) {
+    if let Some(rule) = rules::allowed_rule(&settings.site_rules, &url) {
+        accept_detected_url(app_handle, &url, &format!("regra {}", rule.host));
+        return;
+    }
+    
+    // Regras de bloqueio têm precedência sobre as demais formas de detecção
+    if rules::find_rule(&settings.site_rules, &url).is_some() {
+        return;
+    }
+    
+    // Links de arquivos de mídia e sites de imagens não dependem do yt-dlp
+    if let Some(backend) = backends::special_backend(&settings.backends, &url) {
+        accept_detected_url(app_handle, &url, backend.label());
+        return;
+    }
+    
+    if !settings.ytdlp_detection {
+        return;
+    }
+    
+    let Some(host) = extractors::url_host(&url) else {
+        return;
+    };
+    
+    let cache = app_handle.state::<Arc<ExtractorCache>>().inner().clone();
+    let ttl = Duration::from_secs(settings.extractor_cache_ttl_hours * 3600);
+    
+    match cache.lookup(&host, &url, ttl) {
+        Some(Detection::Supported(extractor)) => {
+            accept_detected_url(app_handle, &url, &extractor);
+        }
+        Some(Detection::Unsupported) => {}
+        None => {
+            let app_handle = app_handle.clone();
+            let ytdlp = settings.ytdlp_path.clone();
+            
+            // Uma consulta por host de cada vez; URLs copiadas durante ela voltam na mesma tarefa
+            tauri::async_runtime::spawn(async move {
+                for (url, extractor) in cache.query(&ytdlp, &host, &url).await {
+                    accept_detected_url(&app_handle, &url, &extractor);
+                }
+            });
+        }
+    }
+}
+
+// Agenda o download automático, se o modo configurado aceitar a URL
+fn schedule_auto_download(app_handle: &AppHandle, url: &str) {
+    let core = app_handle.state::<Arc<Core>>();
+    let settings = core.settings.get();
+    let pending = app_handle.state::<Arc<PendingDownloads>>();
+    
+    // A regra do site pode forçar ou desligar o modo automático
+    let enabled = rules::allowed_rule(&settings.site_rules, url)
+        .and_then(|rule| rule.auto_download)
+        .unwrap_or_else(|| settings.auto_download.applies_to(url));
+    
+    if !enabled {
+        return;
+    }
+    
+    if settings.check_duplicates && core.history.contains(url) {
+        return;
+    }
+    
+    let delay = settings.auto_download.delay_secs;
+    if delay == 0 {
+        core.queue_download(url, None);
+        return;
+    }
+    
+    pending.schedule(url.to_string(), Duration::from_secs(delay));
+    
+    core.notify(
+        "Download Automático",
+        &format!(
+            "Iniciando em {}s. Use \"Desfazer\" no menu do systray para cancelar.",
+            delay
+        ),
+    );
+}
+
+fn undo_auto_downloads(app_handle: &AppHandle) {
+    let cancelled = app_handle.state::<Arc<PendingDownloads>>().cancel_all();
+    let plural = if cancelled > 1 { "s" } else { "" };
+    
+    app_handle.state::<Arc<Core>>().notify(
+        "Download Automático",
+        &if cancelled > 0 {
+            format!("{} download{} cancelado{}", cancelled, plural, plural)
+        } else {
+            "Nenhum download automático pendente".to_string()
+        },
+    );
+}
+
+// Sem o subsistema de console, a saída da CLI no Windows precisa do console de quem a chamou
+#[cfg(target_os = "windows")]
+fn attach_parent_console() {
+    use windows::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+    unsafe {
+        let _ = AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
 fn main() {
-    let settings_manager = Arc::new(SettingsManager::new());
-    let history_manager = Arc::new(HistoryManager::new());
-    let download_queue = Arc::new(DownloadQueue::new());
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        #[cfg(target_os = "windows")]
+        attach_parent_console();
+        
+        if let Some(code) = cli::run(&args) {
+            std::process::exit(code);
+        }
+    }
+    
+    // Tray ou daemon já em execução: os dois não podem dividir a mesma fila
+    if ipc::instance_running() {
+        eprintln!("O ytdl-monitor já está em execução.");
+        return;
+    }
+    
+    let pending_downloads = Arc::new(PendingDownloads::default());
+    let extractor_cache = Arc::new(ExtractorCache::default());
     
     tauri::Builder::default()
         .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_clipboard_manager::init())
-        .manage(settings_manager.clone())
-        .manage(history_manager.clone())
-        .manage(download_queue.clone())
+        .plugin(tauri_plugin_dialog::init())
+        .manage(pending_downloads.clone())
+        .manage(extractor_cache.clone())
+        .manage(ApiServer::default())
+        .manage(Arc::new(RecentUrls::default()))
         .invoke_handler(tauri::generate_handler![
             get_settings,
             save_settings,
+            preview_filename_template,
+            default_download_dir,
+            add_download,
             get_download_status,
+            cancel_download,
+            pause_download,
+            resume_download,
+            set_download_priority,
+            move_download_up,
+            move_download_down,
+            download_next,
+            pin_download,
+            retry_download,
+            dismiss_failed_download,
+            get_history,
+            search_history,
+            delete_history_entry,
+            get_download_log,
+            get_recent_urls,
+            queue_recent_urls,
+            remove_recent_url,
+            clear_recent_urls,
             open_settings_window,
             open_progress_window,
+            open_history_window,
+            open_recent_window,
+            open_quit_window,
+            quit_app,
         ])
         .setup(move |app| {
-            let last_url = Arc::new(Mutex::new(String::new()));
+            let app_handle = app.handle().clone();
+            
+            // O tray é um cliente do núcleo, como o daemon
+            let core = Core::new(Arc::new(TraySink { app: app_handle.clone() }));
+            app.manage(core.clone());
             
             // Criar menu do tray
-            let progress_item = MenuItem::with_id(app, "progress", "📊 Ver Progresso", true, None::<&str>)?;
-            let settings_item = MenuItem::with_id(app, "settings", "⚙️ Configurações", true, None::<&str>)?;
-            let quit_item = MenuItem::with_id(app, "quit", "❌ Sair", true, None::<&str>)?;
-
-            let menu = Menu::with_items(app, &[
-                &progress_item,
-                &settings_item,
-                &quit_item,
-            ])?;
-
-            let app_handle = app.handle().clone();
+            let menu = build_tray_menu(&app_handle, &core.settings.get(), &[])?;
+            
+            // Iniciar agendador da fila
+            tauri::async_runtime::spawn(core.clone().run_scheduler());
+            
+            // API local e socket da CLI; os dois servidores precisam do runtime tokio do Tauri
+            tauri::async_runtime::spawn({
+                let core = core.clone();
+                let app_handle = app_handle.clone();
+                async move {
+                    app_handle.state::<ApiServer>().apply(&core, &core.settings.get().api);
+                    
+                    // Subcomandos da CLI falam com o tray pelo socket local
+                    if let Err(e) = ipc::serve_local(core.clone()).await {
+                        core.notify("Linha de Comando", &format!("Socket local indisponível: {}", e));
+                    }
+                }
+            });
             
-            // Iniciar processador de fila
-            process_download_queue(
-                download_queue.clone(),
-                app_handle.clone(),
-                settings_manager.clone(),
-                history_manager.clone(),
-            );
+            core.notify_restored();
             
             // Criar tray icon
-            let _tray = TrayIconBuilder::new()
+            let _tray = TrayIconBuilder::with_id(TRAY_ID)
                 .menu(&menu)
                 .show_menu_on_left_click(false)
                 .icon(app.default_window_icon().unwrap().clone())
                 .tooltip("YT-DLP Monitor - Clique para baixar")
                 .on_menu_event({
-                    //let app_handle = app_handle.clone();
-                    
                     move |app_local, event| {
                         match event.id.as_ref() {
                             "progress" => {
                                 open_progress_window(app_local.clone());
                             }
+                            "history" => {
+                                open_history_window(app_local.clone());
+                            }
                             "settings" => {
                                 open_settings_window(app_local.clone());
                             }
+                            "undo_auto" => {
+                                undo_auto_downloads(app_local);
+                            }
+                            "recent_all" => {
+                                let urls: Vec<String> = app_local.state::<Arc<RecentUrls>>()
+                                    .list()
+                                    .into_iter()
+                                    .map(|entry| entry.url)
+                                    .collect();
+                                app_local.state::<Arc<Core>>().queue_downloads(&urls, None);
+                            }
+                            "recent_pick" => {
+                                open_recent_window(app_local.clone());
+                            }
+                            "recent_clear" => {
+                                clear_recent_urls(app_local.clone());
+                            }
                             "quit" => {
-                                std::process::exit(0);
+                                // Sem downloads ativos não há o que perguntar
+                                if app_local.state::<Arc<Core>>().queue.active_count() == 0 {
+                                    ipc::remove_socket();
+                                    app_local.exit(0);
+                                } else {
+                                    open_quit_window(app_local.clone());
+                                }
+                            }
+                            id => {
+                                if let Some(profile) = id.strip_prefix(PROFILE_MENU_PREFIX) {
+                                    let url = latest_url(app_local);
+                                    app_local.state::<Arc<Core>>().queue_download(&url, Some(profile.to_string()));
+                                } else if let Some(url) = id.strip_prefix(RECENT_MENU_PREFIX) {
+                                    app_local.state::<Arc<Core>>().queue_download(url, None);
+                                }
                             }
-                            _ => {}
                         }
                     }
                 })
-                // .on_menu_event(move |_app, event| {
-                //     match event.id.as_ref() {
-                //         "quit" => {
-                //             std::process::exit(0);
-                //         }
-                //         _ => {}
-                //     }
-                // })
                 .on_tray_icon_event({
-                    let last_url = last_url.clone();
-                    let download_queue = download_queue.clone();
-                    let settings_manager = settings_manager.clone();
-                    let history_manager = history_manager.clone();
-                    let app_handle = app_handle.clone();
+                    let core = core.clone();
                     
-                    move |_tray, event| {
+                    move |tray, event| {
                         if let tauri::tray::TrayIconEvent::Click { 
                             button: MouseButton::Left, 
                             button_state: MouseButtonState::Up, 
                             .. 
                         } = event {
-                            let url = last_url.lock().unwrap().clone();
-                            let settings = settings_manager.get();
-                            
-                            if url.is_empty() {
-                                if settings.enable_notifications {
-                                    let _ = app_handle.notification()
-                                        .builder()
-                                        .title("Sem URL")
-                                        .body("Copie uma URL de vídeo primeiro")
-                                        .show();
-                                }
-                                return;
-                            }
-                            
-                            if settings.check_duplicates && history_manager.contains(&url) {
-                                if settings.enable_notifications {
-                                    let _ = app_handle.notification()
-                                        .builder()
-                                        .title("Vídeo Já Baixado")
-                                        .body("Este vídeo já foi baixado anteriormente")
-                                        .show();
-                                }
-                                return;
-                            }
-                            
-                            if download_queue.add(url.clone()) {
-                                let status = download_queue.get_status();
-                                
-                                if settings.enable_notifications {
-                                    let _ = app_handle.notification()
-                                        .builder()
-                                        .title("Adicionado à Fila")
-                                        .body(format!(
-                                            "{} ativo{}, {} na fila",
-                                            status.active,
-                                            if status.active > 1 { "s" } else { "" },
-                                            status.queued
-                                        ))
-                                        .show();
-                                }
-                            }
+                            let url = latest_url(tray.app_handle());
+                            core.queue_download(&url, None);
                         }
                     }
                 })
                 .build(app)?;
 
             // Thread para monitorar a área de transferência
-            let monitor_last_url = last_url.clone();
+            let monitor_core = core.clone();
+            let monitor_pending = pending_downloads.clone();
+            let monitor_app = app_handle.clone();
             
             thread::spawn(move || {
-                let mut previous_clipboard = String::new();
+                let mut watcher = ClipboardWatcher::default();
 
                 loop {
                     thread::sleep(Duration::from_millis(500));
 
-                    #[cfg(target_os = "windows")]
-                    if let Some(clipboard_content) = get_clipboard_text() {
-                        if clipboard_content != previous_clipboard {
-                            previous_clipboard = clipboard_content.clone();
+                    let settings = monitor_core.settings.get();
+                    watcher.set_selections(clipboard_selections(&settings));
 
-                            if is_video_url(&clipboard_content) {
-                                *monitor_last_url.lock().unwrap() = clipboard_content.clone();
-                            }
+                    // Um texto copiado pode ter várias URLs; todas entram no histórico
+                    for clipboard_content in watcher.poll() {
+                        for url in recent_urls::extract_urls(&clipboard_content) {
+                            handle_clipboard_url(&monitor_app, url, &settings);
                         }
                     }
+
+                    // Downloads automáticos cuja janela de desfazer terminou
+                    for url in monitor_pending.take_due() {
+                        monitor_core.queue_download(&url, None);
+                    }
                 }
             });
 